@@ -1,26 +1,201 @@
 use clap::Parser;
-use projct_core::{Args, ProjectTreeGenerator};
+use projct_core::config::{self, ColorMode, LAST_RUN_PATH};
+use projct_core::format::FileUtils;
+use projct_core::ignore::lint_ignores;
+use projct_core::report::{Status, audit, cache_stats, clear_cache, diagnose};
+use projct_core::{Args, Config, Error, ProjectTreeGenerator, Result};
+use std::io::{self, IsTerminal};
+use std::path::Path;
+
+const COMMANDS: &[&str] = &[
+    "init",
+    "rerun",
+    "cache",
+    "config",
+    "lint-ignores",
+    "audit",
+    "doctor",
+    "tree",
+];
 
 fn main() {
-    let args = Args::parse();
-    let command_or_path = args.command_or_path.clone();
-    let path = args.path.clone();
-    let (command, effective_path) = match (command_or_path, path) {
-        (Some(cop), Some(p)) => (Some(cop), p),
-        (Some(cop), None) if cop == "init" => (Some(cop), ".".to_string()),
-        (Some(cop), None) => (None, cop),
-        (None, Some(p)) => (None, p),
-        (None, None) => (None, ".".to_string()),
+    if let Err(e) = run(Args::parse()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    if let Some(dir) = &args.chdir {
+        std::env::set_current_dir(dir).map_err(|source| Error::Chdir {
+            path: dir.into(),
+            source,
+        })?;
+    }
+    let (command, paths) = match args.command_or_path.clone() {
+        Some(cop) if COMMANDS.contains(&cop.as_str()) => (Some(cop), args.paths.clone()),
+        Some(cop) => (None, [vec![cop], args.paths.clone()].concat()),
+        None => (None, args.paths.clone()),
     };
 
-    if let Some(cmd) = command {
-        if cmd == "init" {
-            projct_core::config::Config::create_config(&args.config);
-            return;
+    if args.print_default_config {
+        print!("{}", config::default_config_toml());
+        return Ok(());
+    }
+
+    if command.as_deref() == Some("config") {
+        return match paths.first().map(String::as_str) {
+            Some("default") => {
+                print!("{}", config::default_config_toml());
+                Ok(())
+            }
+            _ => Err(Error::Usage(
+                "Unknown config action (expected 'config default')".into(),
+            )),
+        };
+    }
+
+    if command.as_deref() == Some("init") {
+        return Config::create_config(&args.config);
+    }
+
+    if command.as_deref() == Some("cache") {
+        return run_cache_command(paths.first().map(String::as_str));
+    }
+
+    if command.as_deref() == Some("lint-ignores") {
+        let config = Config::new(&args.config, &args, paths)?;
+        let issues: Vec<_> = config
+            .roots()
+            .iter()
+            .flat_map(|root| lint_ignores(Path::new(root)))
+            .collect();
+        for issue in &issues {
+            println!("{}", issue);
+        }
+        if !issues.is_empty() {
+            return Err(Error::LintFindings(issues.len()));
+        }
+        return Ok(());
+    }
+
+    if command.as_deref() == Some("audit") {
+        return run_audit(&args, paths);
+    }
+
+    if command.as_deref() == Some("doctor") {
+        return run_doctor(&args, paths);
+    }
+
+    if command.as_deref() == Some("tree") {
+        let mut config = Config::new(&args.config, &args, paths)?;
+        if let Some(levels) = args.max_depth {
+            config.general.max_depth = Some(levels.saturating_sub(1));
         }
+        let color = match args.color.unwrap_or_default() {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        };
+        return ProjectTreeGenerator::new(config).write_tree_only(&mut io::stdout().lock(), color);
     }
 
-    let config = projct_core::config::Config::new(&args.config, &args, effective_path);
+    let config = if command.as_deref() == Some("rerun") {
+        if !Path::new(LAST_RUN_PATH).exists() {
+            return Err(Error::NoLastRun {
+                path: LAST_RUN_PATH.into(),
+            });
+        }
+        if args.edit {
+            Config::edit_file(LAST_RUN_PATH)?;
+        }
+        Config::new(LAST_RUN_PATH, &args, paths)?
+    } else {
+        Config::new(&args.config, &args, paths)?
+    };
     let generator = ProjectTreeGenerator::new(config);
-    generator.generate();
+    generator.generate()?;
+    generator.config.save_last_run();
+    Ok(())
+}
+
+fn run_audit(args: &Args, paths: Vec<String>) -> Result<()> {
+    let config = Config::new(&args.config, args, paths)?;
+    let roots = config.roots();
+    let mut count = 0;
+    for root in &roots {
+        if !Path::new(root).exists() {
+            return Err(Error::StartPath { path: root.into() });
+        }
+        let mut root_config = config.clone();
+        root_config.general.path = root.clone();
+        root_config.general.paths = vec![];
+        for finding in audit(&ProjectTreeGenerator::new(root_config)) {
+            if roots.len() > 1 {
+                print!("{}/", root.trim_end_matches('/'));
+            }
+            println!("{}", finding);
+            count += 1;
+        }
+    }
+    if count > args.max_findings {
+        return Err(Error::AuditFindings {
+            count,
+            allowed: args.max_findings,
+        });
+    }
+    Ok(())
+}
+
+fn run_doctor(args: &Args, paths: Vec<String>) -> Result<()> {
+    let diagnostics = diagnose(&args.config, args, paths);
+    for diagnostic in &diagnostics {
+        let label = match diagnostic.status {
+            Status::Ok => "ok",
+            Status::Warning => "warn",
+            Status::Failure => "FAIL",
+        };
+        println!("{:<5} {}: {}", label, diagnostic.name, diagnostic.detail);
+        if let Some(fix) = &diagnostic.fix {
+            println!("      fix: {}", fix);
+        }
+    }
+    let failures = diagnostics
+        .iter()
+        .filter(|d| d.status == Status::Failure)
+        .count();
+    if failures > 0 {
+        return Err(Error::DoctorFindings(failures));
+    }
+    Ok(())
+}
+
+fn run_cache_command(action: Option<&str>) -> Result<()> {
+    match action {
+        None | Some("stats") => {
+            let stats = cache_stats()?;
+            println!("Cache directory: {}", stats.path.display());
+            println!(
+                "{} files, {}",
+                stats.files,
+                FileUtils::format_size(stats.bytes)
+            );
+        }
+        Some("clear") => {
+            let removed = clear_cache()?;
+            println!(
+                "Removed {} files ({}) from {}",
+                removed.files,
+                FileUtils::format_size(removed.bytes),
+                removed.path.display()
+            );
+        }
+        Some(other) => {
+            return Err(Error::Usage(format!(
+                "Unknown cache action '{}' (expected 'stats' or 'clear')",
+                other
+            )));
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,42 @@
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn chdir_anchors_config_output_and_patterns() {
+    let base = std::env::temp_dir().join(format!("projct-chdir-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&base);
+    let work = base.join("work");
+    for (path, content) in [
+        (
+            "cfg/projct.toml",
+            "[filters]\nexclude_patterns = [\"*.log\"]\n",
+        ),
+        ("src/main.rs", "fn main() {}\n"),
+        ("src/debug.log", "noise\n"),
+        ("src/notes/todo.md", "later\n"),
+        ("out/.keep", ""),
+    ] {
+        let path = work.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    let elsewhere = base.join("elsewhere");
+    fs::create_dir_all(&elsewhere).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_projct-cli"))
+        .current_dir(&elsewhere)
+        .args(["-C", "../work", "-c", "cfg/projct.toml", "src"])
+        .args(["-o", "out/result.txt", "--exclude", "notes/"])
+        .status()
+        .unwrap();
+    let output = fs::read_to_string(work.join("out/result.txt"));
+    let stray = fs::read_dir(&elsewhere).unwrap().count();
+    let _ = fs::remove_dir_all(&base);
+
+    assert!(status.success());
+    let output = output.unwrap();
+    assert!(output.contains("main.rs:\nfn main() {}"), "{}", output);
+    assert!(!output.contains("debug.log:\n"), "{}", output);
+    assert!(!output.contains("todo.md"), "{}", output);
+    assert_eq!(stray, 0);
+}
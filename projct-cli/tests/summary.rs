@@ -0,0 +1,29 @@
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn summary_prints_one_line_unless_full_is_requested() {
+    let base = std::env::temp_dir().join(format!("projct-summary-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&base);
+    fs::create_dir_all(base.join("src")).unwrap();
+    fs::write(base.join("src/main.rs"), "fn main() {}\n").unwrap();
+
+    let stderr = |mode: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_projct-cli"))
+            .current_dir(&base)
+            .args(["src", "-o", "out.txt"])
+            .args(mode)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let default = stderr(&[]);
+    let full = stderr(&["--summary", "full"]);
+    let _ = fs::remove_dir_all(&base);
+
+    assert!(!default.contains("Summary:"), "{}", default);
+    assert!(default.contains("Wrote out.txt (1 file, "), "{}", default);
+    assert!(full.contains("Summary:\n- directories: 1\n"), "{}", full);
+    assert!(full.contains("Wrote out.txt (1 file, "), "{}", full);
+}
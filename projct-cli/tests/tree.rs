@@ -0,0 +1,33 @@
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn tree_level_flag_counts_levels_like_tree() {
+    let base = std::env::temp_dir().join(format!("projct-tree-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&base);
+    fs::create_dir_all(base.join("src/bin")).unwrap();
+    fs::write(base.join("src/lib.rs"), "pub fn f() {}\n").unwrap();
+    fs::write(base.join("src/bin/main.rs"), "fn main() {}\n").unwrap();
+
+    let tree = |levels: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_projct-cli"))
+            .current_dir(&base)
+            .args(["tree", "--color", "never", "-L", levels])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let one = tree("1");
+    let two = tree("2");
+    let _ = fs::remove_dir_all(&base);
+
+    assert!(
+        one.contains("└── src/\n    └── … (2 entries hidden, depth limit reached)\n"),
+        "{}",
+        one
+    );
+    assert!(two.contains("    ├── bin/\n"), "{}", two);
+    assert!(two.contains("    │   └── … (1 entry hidden"), "{}", two);
+    assert!(two.contains("    └── lib.rs\n"), "{}", two);
+}
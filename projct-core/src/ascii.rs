@@ -0,0 +1,99 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+pub struct AsciiWriter<'a> {
+    inner: &'a mut dyn Write,
+    pending: Vec<u8>,
+}
+
+impl<'a> AsciiWriter<'a> {
+    pub fn new(inner: &'a mut dyn Write) -> Self {
+        AsciiWriter {
+            inner,
+            pending: vec![],
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        let mut out = String::new();
+        for byte in self.pending.drain(..) {
+            let _ = write!(out, "\\x{:02x}", byte);
+        }
+        self.inner.write_all(out.as_bytes())?;
+        self.inner.flush()
+    }
+}
+
+impl Write for AsciiWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let mut out = String::new();
+        let mut consumed = 0;
+        loop {
+            match std::str::from_utf8(&self.pending[consumed..]) {
+                Ok(text) => {
+                    push_ascii(&mut out, text);
+                    consumed = self.pending.len();
+                    break;
+                }
+                Err(e) => {
+                    let valid_end = consumed + e.valid_up_to();
+                    let text = std::str::from_utf8(&self.pending[consumed..valid_end]).unwrap();
+                    push_ascii(&mut out, text);
+                    consumed = valid_end;
+                    let Some(invalid_len) = e.error_len() else {
+                        break;
+                    };
+                    for byte in &self.pending[consumed..consumed + invalid_len] {
+                        let _ = write!(out, "\\x{:02x}", byte);
+                    }
+                    consumed += invalid_len;
+                }
+            }
+        }
+        self.pending.drain(..consumed);
+        self.inner.write_all(out.as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub fn to_ascii(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    push_ascii(&mut out, text);
+    out
+}
+
+fn push_ascii(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_ascii() {
+            out.push(c);
+            continue;
+        }
+        match transliterate(c) {
+            Some(replacement) => out.push_str(replacement),
+            None => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    let _ = write!(out, "\\u{:04x}", unit);
+                }
+            }
+        }
+    }
+}
+
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        '├' | '│' => "|",
+        '└' => "`",
+        '─' => "-",
+        '…' => "...",
+        '‘' | '’' => "'",
+        '–' | '—' => "-",
+        '\u{a0}' => " ",
+        _ => return None,
+    })
+}
@@ -0,0 +1,92 @@
+use regex::Regex;
+use std::fmt;
+use std::fs;
+
+use crate::file_utils::FileUtils;
+use crate::filters;
+use crate::generator::ProjectTreeGenerator;
+use crate::redact::Redactor;
+
+const PII_PATTERNS: &[(&str, &str)] = &[
+    (
+        "email address",
+        r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b",
+    ),
+    ("social security number", r"\b\d{3}-\d{2}-\d{4}\b"),
+];
+
+const HOST_PATTERNS: &[(&str, &str)] = &[
+    (
+        "internal hostname",
+        r"(?i)\b[a-z0-9-]+(?:\.[a-z0-9-]+)*\.(?:internal|intranet|corp|lan|local)\b",
+    ),
+    (
+        "private IP address",
+        r"\b(?:10\.\d{1,3}|172\.(?:1[6-9]|2\d|3[01])|192\.168)\.\d{1,3}\.\d{1,3}\b",
+    ),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FindingKind {
+    Secret,
+    Pii,
+    InternalHost,
+}
+
+#[derive(Debug)]
+pub struct Finding {
+    pub file: String,
+    pub line: usize,
+    pub kind: FindingKind,
+    pub detail: &'static str,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            FindingKind::Secret => "possible secret",
+            FindingKind::Pii => "possible PII",
+            FindingKind::InternalHost => "internal host",
+        };
+        write!(f, "{}:{}: {} ({})", self.file, self.line, kind, self.detail)
+    }
+}
+
+pub fn audit(generator: &ProjectTreeGenerator) -> Vec<Finding> {
+    let redactor = Redactor::with_patterns(&generator.config.redact.patterns);
+    let detectors: Vec<(FindingKind, &'static str, Regex)> = PII_PATTERNS
+        .iter()
+        .map(|p| (FindingKind::Pii, p))
+        .chain(HOST_PATTERNS.iter().map(|p| (FindingKind::InternalHost, p)))
+        .map(|(kind, (label, pattern))| (kind, *label, Regex::new(pattern).unwrap()))
+        .collect();
+    let start_path = std::path::Path::new(&generator.config.general.path);
+    let mut findings = vec![];
+    for file_path in generator.content_paths() {
+        let Some((content, _)) = fs::read(&file_path).ok().and_then(FileUtils::decode_text) else {
+            continue;
+        };
+        let file = filters::relative_path(&file_path, start_path);
+        let mut found: Vec<(usize, FindingKind, &'static str)> = redactor
+            .find(&file_path, &content)
+            .into_iter()
+            .map(|(range, label)| (range.start, FindingKind::Secret, label))
+            .collect();
+        for (kind, label, pattern) in &detectors {
+            found.extend(
+                pattern
+                    .find_iter(&content)
+                    .map(|m| (m.start(), *kind, *label)),
+            );
+        }
+        found.sort_by_key(|(offset, _, _)| *offset);
+        found.dedup_by_key(|(offset, kind, _)| (*offset, *kind));
+        findings.extend(found.into_iter().map(|(offset, kind, detail)| Finding {
+            file: file.clone(),
+            line: content[..offset].matches('\n').count() + 1,
+            kind,
+            detail,
+        }));
+    }
+    findings
+}
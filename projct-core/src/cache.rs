@@ -0,0 +1,75 @@
+use directories::ProjectDirs;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::error::{Error, Result};
+use crate::lock;
+
+const LOCK_FILE: &str = ".lock";
+
+pub struct CacheStats {
+    pub path: PathBuf,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+pub fn cache_dir() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "projct").map(|dirs| dirs.cache_dir().to_path_buf())
+}
+
+pub fn lock() -> Result<File> {
+    let dir = cache_dir().ok_or(Error::CacheUnavailable)?;
+    fs::create_dir_all(&dir).map_err(|source| cache_error(&dir, source))?;
+    let lock_path = dir.join(LOCK_FILE);
+    let file = File::create(&lock_path).map_err(|source| cache_error(&lock_path, source))?;
+    lock::lock_exclusive(&file, &dir).map_err(|source| cache_error(&dir, source))?;
+    Ok(file)
+}
+
+pub fn stats() -> Result<CacheStats> {
+    let path = cache_dir().ok_or(Error::CacheUnavailable)?;
+    let mut stats = CacheStats {
+        path,
+        files: 0,
+        bytes: 0,
+    };
+    if !stats.path.exists() {
+        return Ok(stats);
+    }
+    for entry in WalkDir::new(&stats.path).into_iter().filter_map(|e| e.ok()) {
+        if entry.file_type().is_file() && entry.file_name() != LOCK_FILE {
+            stats.files += 1;
+            stats.bytes += entry.metadata().map_or(0, |m| m.len());
+        }
+    }
+    Ok(stats)
+}
+
+pub fn clear() -> Result<CacheStats> {
+    let _lock = lock()?;
+    let removed = stats()?;
+    let entries =
+        fs::read_dir(&removed.path).map_err(|source| cache_error(&removed.path, source))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        if entry.file_name() == LOCK_FILE {
+            continue;
+        }
+        let path = entry.path();
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        result.map_err(|source| cache_error(&path, source))?;
+    }
+    Ok(removed)
+}
+
+fn cache_error(path: &Path, source: io::Error) -> Error {
+    Error::Cache {
+        path: path.to_path_buf(),
+        source,
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+#[cfg(target_os = "macos")]
+const BACKENDS: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(windows)]
+const BACKENDS: &[&[&str]] = &[&["clip"]];
+#[cfg(not(any(target_os = "macos", windows)))]
+const BACKENDS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+pub fn copy(text: &str) -> Result<&'static str, String> {
+    let mut errors = vec![];
+    for backend in BACKENDS {
+        match run_backend(backend, text) {
+            Ok(()) => return Ok(backend[0]),
+            Err(e) => errors.push(format!("{}: {}", backend[0], e)),
+        }
+    }
+    Err(format!(
+        "no clipboard backend worked ({})",
+        errors.join("; ")
+    ))
+}
+
+pub fn backend_names() -> Vec<&'static str> {
+    BACKENDS.iter().map(|backend| backend[0]).collect()
+}
+
+pub fn available_backends() -> Vec<&'static str> {
+    backend_names()
+        .into_iter()
+        .filter(|program| on_path(program))
+        .collect()
+}
+
+fn on_path(program: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        dir.join(program).is_file()
+            || (cfg!(windows) && dir.join(format!("{}.exe", program)).is_file())
+    })
+}
+
+fn run_backend(command: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(command[0])
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("exited with {}", status)))
+    }
+}
@@ -0,0 +1,246 @@
+struct Syntax {
+    line: &'static [&'static str],
+    block: &'static [(&'static str, &'static str)],
+    nested: bool,
+    quotes: &'static [&'static str],
+    char_literals: bool,
+    raw_strings: bool,
+}
+
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+
+const fn c_like(quotes: &'static [&'static str], char_literals: bool) -> Syntax {
+    Syntax {
+        line: &["//"],
+        block: C_BLOCK,
+        nested: false,
+        quotes,
+        char_literals,
+        raw_strings: false,
+    }
+}
+
+const fn hash(quotes: &'static [&'static str]) -> Syntax {
+    Syntax {
+        line: &["#"],
+        block: &[],
+        nested: false,
+        quotes,
+        char_literals: false,
+        raw_strings: false,
+    }
+}
+
+fn syntax(language: &str) -> Option<Syntax> {
+    let syntax = match language {
+        "rust" => Syntax {
+            nested: true,
+            raw_strings: true,
+            ..c_like(&["\""], true)
+        },
+        "c" | "cpp" | "java" | "csharp" | "protobuf" => c_like(&["\"", "'"], true),
+        "go" => c_like(&["\"", "`"], true),
+        "javascript" | "jsx" | "typescript" | "tsx" => c_like(&["\"", "'", "`"], false),
+        "kotlin" | "scala" | "swift" => Syntax {
+            nested: true,
+            ..c_like(&["\"\"\"", "\""], true)
+        },
+        "dart" => c_like(&["\"\"\"", "'''", "\"", "'"], false),
+        "php" | "css" => Syntax {
+            line: if language == "php" { &["//"] } else { &[] },
+            ..c_like(&["\"", "'"], false)
+        },
+        "scss" => c_like(&["\"", "'"], false),
+        "zig" => Syntax {
+            block: &[],
+            ..c_like(&["\""], true)
+        },
+        "python" | "toml" | "elixir" => hash(&["\"\"\"", "'''", "\"", "'"]),
+        "ruby" | "bash" | "yaml" => hash(&["\"", "'"]),
+        "dockerfile" | "makefile" | "cmake" => hash(&[]),
+        "powershell" => Syntax {
+            block: &[("<#", "#>")],
+            ..hash(&["\"", "'"])
+        },
+        "ini" => Syntax {
+            line: &[";", "#"],
+            ..hash(&[])
+        },
+        "sql" => Syntax {
+            line: &["--"],
+            ..c_like(&["'", "\""], false)
+        },
+        "lua" => Syntax {
+            line: &["--"],
+            block: &[("--[[", "]]")],
+            ..c_like(&["\"", "'"], false)
+        },
+        "haskell" => Syntax {
+            line: &["--"],
+            block: &[("{-", "-}")],
+            nested: true,
+            ..c_like(&["\""], true)
+        },
+        "html" | "xml" | "vue" | "svelte" => Syntax {
+            line: &[],
+            block: &[("<!--", "-->")],
+            ..c_like(&[], false)
+        },
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+pub fn strip(language: &str, content: &str) -> Option<String> {
+    let syntax = syntax(language)?;
+    let mut out = String::with_capacity(content.len());
+    let mut starts_in_string = vec![false];
+    let mut i = 0;
+    if syntax.line.contains(&"#") && content.starts_with("#!") {
+        i = content.find('\n').unwrap_or(content.len());
+        out.push_str(&content[..i]);
+    }
+    while i < content.len() {
+        let rest = &content[i..];
+        if let Some((open, close)) = syntax.block.iter().find(|(open, _)| rest.starts_with(open)) {
+            i += open.len();
+            let mut depth = 1;
+            while i < content.len() && depth > 0 {
+                let rest = &content[i..];
+                if rest.starts_with(close) {
+                    depth -= 1;
+                    i += close.len();
+                } else if syntax.nested && rest.starts_with(open) {
+                    depth += 1;
+                    i += open.len();
+                } else {
+                    let c = rest.chars().next().unwrap();
+                    if c == '\n' {
+                        out.push('\n');
+                        starts_in_string.push(false);
+                    }
+                    i += c.len_utf8();
+                }
+            }
+            continue;
+        }
+        if syntax
+            .line
+            .iter()
+            .any(|marker| rest.starts_with(marker) && line_comment_starts(content, i, marker))
+        {
+            i += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        }
+        let string_end = syntax
+            .raw_strings
+            .then(|| raw_string_len(content, i))
+            .flatten()
+            .or_else(|| {
+                syntax
+                    .quotes
+                    .iter()
+                    .find(|quote| rest.starts_with(*quote))
+                    .map(|quote| quoted_len(rest, quote))
+            })
+            .or_else(|| {
+                syntax
+                    .char_literals
+                    .then(|| char_literal_len(rest))
+                    .flatten()
+            });
+        if let Some(len) = string_end {
+            for c in rest[..len].chars() {
+                out.push(c);
+                if c == '\n' {
+                    starts_in_string.push(true);
+                }
+            }
+            i += len;
+            continue;
+        }
+        let c = rest.chars().next().unwrap();
+        out.push(c);
+        if c == '\n' {
+            starts_in_string.push(false);
+        }
+        i += c.len_utf8();
+    }
+
+    let lines: Vec<&str> = out.split('\n').collect();
+    let mut stripped = String::with_capacity(out.len());
+    for (index, line) in lines.iter().enumerate() {
+        let in_string = starts_in_string[index];
+        let ends_in_string = starts_in_string.get(index + 1).copied().unwrap_or(false);
+        if !in_string && line.trim().is_empty() {
+            continue;
+        }
+        stripped.push_str(if ends_in_string {
+            line
+        } else {
+            line.trim_end()
+        });
+        stripped.push('\n');
+    }
+    if !content.ends_with('\n') && stripped.ends_with('\n') {
+        stripped.pop();
+    }
+    Some(stripped)
+}
+
+fn line_comment_starts(content: &str, at: usize, marker: &str) -> bool {
+    let previous = content[..at].chars().next_back();
+    match marker {
+        "#" => previous.is_none_or(char::is_whitespace),
+        "//" => previous != Some(':'),
+        _ => true,
+    }
+}
+
+fn quoted_len(rest: &str, quote: &str) -> usize {
+    let mut chars = rest.char_indices().skip(quote.chars().count());
+    while let Some((index, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if rest[index..].starts_with(quote) {
+            return index + quote.len();
+        }
+    }
+    rest.len()
+}
+
+fn char_literal_len(rest: &str) -> Option<usize> {
+    if !rest.starts_with('\'') {
+        return None;
+    }
+    let mut chars = rest.char_indices().skip(1);
+    let (_, first) = chars.next()?;
+    if first == '\\' {
+        return rest
+            .get(3..)?
+            .char_indices()
+            .take(10)
+            .find(|&(_, c)| c == '\'')
+            .map(|(index, _)| index + 4);
+    }
+    match chars.next() {
+        Some((index, '\'')) if first != '\'' && first != '\n' => Some(index + 1),
+        _ => None,
+    }
+}
+
+fn raw_string_len(content: &str, at: usize) -> Option<usize> {
+    let rest = &content[at..];
+    let after_prefix = rest.strip_prefix("br").or_else(|| rest.strip_prefix('r'))?;
+    let previous = content[..at].chars().next_back();
+    if previous.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    let hashes = after_prefix.len() - after_prefix.trim_start_matches('#').len();
+    let body = after_prefix[hashes..].strip_prefix('"')?;
+    let close = format!("\"{}", "#".repeat(hashes));
+    let end = body
+        .find(&close)
+        .map_or(body.len(), |end| end + close.len());
+    Some(rest.len() - body.len() + end)
+}
@@ -1,36 +1,300 @@
-use clap::Parser;
-use serde::Deserialize;
-use std::fs::File;
+use clap::{Parser, ValueEnum};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
-const CONFIG: &str = r#"[general]
-path = "."
+use crate::config_template;
+pub use crate::config_template::{default_config_toml, FieldDoc, FIELD_DOCS};
+use crate::error::{Error, Result};
+use crate::file_utils::FileUtils;
+use crate::filters;
+use crate::naming::DEFAULT_NAME_TEMPLATE;
+use crate::permissions::PermissionFilter;
 
-[output]
-filename = "output.txt"
-"#;
+pub const STATE_DIR: &str = ".projct";
+pub const LAST_RUN_PATH: &str = ".projct/last-run.toml";
+
+#[cfg(windows)]
+const DEFAULT_MAX_PATH_LENGTH: usize = 260;
+#[cfg(target_os = "macos")]
+const DEFAULT_MAX_PATH_LENGTH: usize = 1024;
+#[cfg(not(any(windows, target_os = "macos")))]
+const DEFAULT_MAX_PATH_LENGTH: usize = 4096;
+
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Markdown,
+    Json,
+    #[serde(rename = "xml-docs")]
+    #[value(name = "xml-docs")]
+    XmlDocs,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Text => "txt",
+            OutputFormat::Markdown => "md",
+            OutputFormat::Json => "json",
+            OutputFormat::XmlDocs => "xml",
+        }
+    }
+
+    pub fn default_filename(self) -> String {
+        format!("output.{}", self.extension())
+    }
+}
+
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FrontMatterMode {
+    #[default]
+    Keep,
+    Strip,
+    Summarize,
+}
+
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenizerKind {
+    #[default]
+    Cl100k,
+    Heuristic,
+}
+
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnonymizeMode {
+    #[default]
+    Off,
+    Generalize,
+    Hash,
+}
+
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Name,
+    Size,
+    Mtime,
+    Extension,
+}
+
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentOrder {
+    #[default]
+    Tree,
+    Size,
+    Mtime,
+    Alpha,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TruncateLines {
+    pub head: usize,
+    pub tail: usize,
+}
+
+impl std::str::FromStr for TruncateLines {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (head, tail) = s
+            .split_once(',')
+            .ok_or_else(|| format!("expected HEAD,TAIL line counts, got '{}'", s))?;
+        let parse = |n: &str| {
+            n.trim()
+                .parse()
+                .map_err(|_| format!("invalid line count '{}'", n))
+        };
+        Ok(TruncateLines {
+            head: parse(head)?,
+            tail: parse(tail)?,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TreeStyle {
+    #[default]
+    Unicode,
+    Ascii,
+    Indent,
+}
+
+impl TreeStyle {
+    pub fn glyphs(self) -> (&'static str, &'static str, &'static str, &'static str) {
+        match self {
+            TreeStyle::Unicode => ("├── ", "└── ", "│   ", "    "),
+            TreeStyle::Ascii => ("|-- ", "`-- ", "|   ", "    "),
+            TreeStyle::Indent => ("  ", "  ", "  ", "  "),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymlinkMode {
+    #[default]
+    Follow,
+    Skip,
+    ShowAsLink,
+}
+
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryMode {
+    Off,
+    #[default]
+    Print,
+    Full,
+    Append,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BinaryDetection {
+    #[default]
+    Fast,
+    Full,
+}
 
 #[derive(Deserialize, Debug, Default)]
 struct RawGeneral {
     path: Option<String>,
     max_depth: Option<u32>,
+    overview_depth: Option<u32>,
     use_gitignore: Option<bool>,
     show_ignored: Option<bool>,
+    hidden: Option<bool>,
     show_binary: Option<bool>,
+    list_binary: Option<bool>,
+    max_path_length: Option<usize>,
+    from_json: Option<String>,
+    symlinks: Option<SymlinkMode>,
+    binary_detection: Option<BinaryDetection>,
+    extra_ignore_files: Option<Vec<String>>,
+    git_tracked: Option<bool>,
+    changed_since: Option<String>,
+    full_tree: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_size")]
+    skip_content_over_dir_size: Option<u64>,
+    count_excluded: Option<bool>,
+    exclude_config: Option<bool>,
+    reproducible: Option<bool>,
+    paths: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Debug, Default)]
 struct RawOutput {
     filename: Option<String>,
     max_file_size: Option<u64>,
+    truncate_lines: Option<TruncateLines>,
     show_line_numbers: Option<bool>,
+    name_template: Option<String>,
+    header: Option<String>,
+    footer: Option<String>,
+    append: Option<bool>,
+    format: Option<OutputFormat>,
+    front_matter: Option<FrontMatterMode>,
+    docs_only: Option<bool>,
+    lfs_fetch_max_size: Option<u64>,
+    tokens: Option<bool>,
+    tokenizer: Option<TokenizerKind>,
+    clipboard: Option<bool>,
+    annotate: Option<bool>,
+    split_size: Option<u64>,
+    split_tokens: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_size")]
+    max_total_size: Option<u64>,
+    truncate_at_limit: Option<bool>,
+    timings: Option<bool>,
+    commit_info: Option<bool>,
+    manifest: Option<String>,
+    licenses: Option<bool>,
+    summary: Option<SummaryMode>,
+    ascii: Option<bool>,
+    tree_style: Option<TreeStyle>,
+    sort: Option<SortOrder>,
+    reverse: Option<bool>,
+    content_order: Option<ContentOrder>,
+    group_order: Option<Vec<String>>,
+    entropy_threshold: Option<f64>,
+    anonymize: Option<AnonymizeMode>,
+    language_map: Option<BTreeMap<String, String>>,
+    path_aliases: Option<BTreeMap<String, String>>,
+    #[serde(default, deserialize_with = "deserialize_size_map")]
+    max_size_by_ext: Option<BTreeMap<String, u64>>,
 }
 
 #[derive(Deserialize, Debug, Default)]
 struct RawFilters {
     include_patterns: Option<Vec<String>>,
     exclude_patterns: Option<Vec<String>>,
+    deep_patterns: Option<Vec<String>>,
+    default_excludes: Option<bool>,
+    summarize_patterns: Option<Vec<String>>,
+    exclude_owners: Option<Vec<String>>,
+    required_mode: Option<String>,
+    include_groups: Option<Vec<String>>,
+    exclude_groups: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct RawIo {
+    max_open_files: Option<usize>,
+    bytes_per_sec: Option<u64>,
+    max_buffer_size: Option<u64>,
+    write_buffer_size: Option<usize>,
+    read_workers: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct RawSampling {
+    patterns: Option<Vec<String>>,
+    count: Option<usize>,
+    seed: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct RawRedact {
+    enabled: Option<bool>,
+    patterns: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct RawTransform {
+    strip_comments: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct RawHooks {
+    pre: Option<String>,
+    post: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct RawNotifications {
+    desktop: Option<bool>,
+    webhook: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Default)]
@@ -38,67 +302,611 @@ struct RawConfig {
     general: Option<RawGeneral>,
     output: Option<RawOutput>,
     filters: Option<RawFilters>,
+    io: Option<RawIo>,
+    sampling: Option<RawSampling>,
+    redact: Option<RawRedact>,
+    transform: Option<RawTransform>,
+    hooks: Option<RawHooks>,
+    notifications: Option<RawNotifications>,
+    groups: Option<BTreeMap<String, Vec<String>>>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Clone, Debug)]
 pub struct General {
     pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_depth: Option<u32>,
+    pub overview_depth: u32,
     pub use_gitignore: bool,
     pub show_ignored: bool,
+    pub hidden: bool,
     pub show_binary: bool,
+    pub list_binary: bool,
+    pub max_path_length: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_json: Option<String>,
+    pub symlinks: SymlinkMode,
+    pub binary_detection: BinaryDetection,
+    pub extra_ignore_files: Vec<String>,
+    pub git_tracked: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_since: Option<String>,
+    pub full_tree: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip_content_over_dir_size: Option<u64>,
+    pub count_excluded: bool,
+    pub exclude_config: bool,
+    pub reproducible: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Clone, Debug)]
 pub struct Output {
     pub filename: String,
     pub max_file_size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncate_lines: Option<TruncateLines>,
     pub show_line_numbers: bool,
+    pub name_template: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub footer: Option<String>,
+    pub append: bool,
+    pub format: OutputFormat,
+    pub front_matter: FrontMatterMode,
+    pub docs_only: bool,
+    pub lfs_fetch_max_size: u64,
+    pub tokens: bool,
+    pub tokenizer: TokenizerKind,
+    pub clipboard: bool,
+    pub annotate: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_tokens: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_total_size: Option<u64>,
+    pub truncate_at_limit: bool,
+    pub timings: bool,
+    pub commit_info: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<String>,
+    pub licenses: bool,
+    pub summary: SummaryMode,
+    pub ascii: bool,
+    pub tree_style: TreeStyle,
+    pub sort: SortOrder,
+    pub reverse: bool,
+    pub content_order: ContentOrder,
+    pub group_order: Vec<String>,
+    pub entropy_threshold: f64,
+    pub anonymize: AnonymizeMode,
+    pub language_map: BTreeMap<String, String>,
+    pub path_aliases: BTreeMap<String, String>,
+    pub max_size_by_ext: BTreeMap<String, u64>,
+}
+
+impl Output {
+    pub fn max_file_size_for(&self, path: &Path) -> u64 {
+        path.extension()
+            .and_then(|e| {
+                self.max_size_by_ext
+                    .get(&e.to_string_lossy().to_lowercase())
+            })
+            .copied()
+            .unwrap_or(self.max_file_size)
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Clone, Debug)]
 pub struct Filters {
     pub include_patterns: Vec<String>,
     pub exclude_patterns: Vec<String>,
+    pub deep_patterns: Vec<String>,
+    pub default_excludes: bool,
+    pub summarize_patterns: Vec<String>,
+    pub exclude_owners: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required_mode: Option<String>,
+    pub include_groups: Vec<String>,
+    pub exclude_groups: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Io {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_open_files: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_per_sec: Option<u64>,
+    pub max_buffer_size: u64,
+    pub write_buffer_size: usize,
+    pub read_workers: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Sampling {
+    pub patterns: Vec<String>,
+    pub count: usize,
+    pub seed: u64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Clone, Debug)]
+pub struct Redact {
+    pub enabled: bool,
+    pub patterns: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Transform {
+    pub strip_comments: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Hooks {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post: Option<String>,
+    #[serde(skip)]
+    pub run: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Notifications {
+    pub desktop: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
 pub struct Config {
     pub general: General,
     pub output: Output,
     pub filters: Filters,
+    pub io: Io,
+    pub sampling: Sampling,
+    pub redact: Redact,
+    pub transform: Transform,
+    pub hooks: Hooks,
+    pub notifications: Notifications,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, Vec<String>>,
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
 }
 
 #[derive(Parser, Clone)]
 #[command(about = "Generate directory tree with file contents")]
 pub struct Args {
-    #[arg(help = "Command to execute (use 'init' to create config) or starting path")]
+    #[arg(
+        help = "Command to execute ('init' to create config, 'rerun' to repeat the last run, 'cache stats|clear', 'config default', 'lint-ignores', 'audit', 'doctor', 'tree') or starting path"
+    )]
     pub command_or_path: Option<String>,
 
-    #[arg(help = "Starting path if command is provided")]
-    pub path: Option<String>,
+    #[arg(help = "More starting paths, or the starting path if a command is provided")]
+    pub paths: Vec<String>,
+
+    #[arg(
+        short = 'C',
+        long,
+        help = "Change to this directory before doing anything else"
+    )]
+    pub chdir: Option<String>,
 
-    #[arg(long, help = "Maximum depth to traverse")]
+    #[arg(short = 'L', long, help = "Maximum depth to traverse")]
     pub max_depth: Option<u32>,
 
+    #[arg(
+        short = 'I',
+        long,
+        value_name = "GLOB",
+        help = "Exclude files and directories matching this pattern; '|' separates alternatives (repeatable)"
+    )]
+    pub exclude: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Show the tree only to --overview-depth, plus contents of files matching this pattern (repeatable)"
+    )]
+    pub deep: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Levels of the tree shown outside --deep paths (default 2)"
+    )]
+    pub overview_depth: Option<u32>,
+
     #[arg(long, default_value_t = 100000, help = "Maximum file size to display")]
     pub max_size: u64,
 
+    #[arg(
+        long,
+        value_name = "HEAD,TAIL",
+        help = "Show the first HEAD and last TAIL lines of files over --max-size instead of skipping them"
+    )]
+    pub truncate_lines: Option<TruncateLines>,
+
     #[arg(long, help = "Show line numbers")]
     pub line_numbers: bool,
 
-    #[arg(long, help = "Ignore .gitignore files")]
+    #[arg(long, help = "Ignore .gitignore and .projctignore files")]
     pub no_gitignore: bool,
 
-    #[arg(long, help = "Show ignored files")]
+    #[arg(
+        long,
+        help = "Do not exclude .env, *.pem, *.key, id_rsa* and credentials.json by default"
+    )]
+    pub no_default_excludes: bool,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Replace the contents of files matching this pattern by a one-line summary (repeatable)"
+    )]
+    pub summarize: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Show lockfiles and generated files in full instead of summarizing them"
+    )]
+    pub no_summarize: bool,
+
+    #[arg(
+        long,
+        help = "Include the config file itself when it lies inside the scanned directory"
+    )]
+    pub include_config: bool,
+
+    #[arg(
+        long,
+        value_name = "USER",
+        help = "Skip contents of files owned by this user name or uid (repeatable)"
+    )]
+    pub exclude_owner: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "GROUP",
+        help = "Only include contents of files in this [groups] entry (repeatable)"
+    )]
+    pub include_group: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "GROUP",
+        help = "Skip files and directories in this [groups] entry (repeatable)"
+    )]
+    pub exclude_group: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Also read ignore patterns from files with this name, e.g. .dockerignore (repeatable)"
+    )]
+    pub extra_ignore_file: Vec<String>,
+
+    #[cfg(feature = "git")]
+    #[arg(long, help = "Only include files tracked by git (git ls-files)")]
+    pub git_tracked: bool,
+
+    #[cfg(feature = "git")]
+    #[arg(
+        long,
+        value_name = "REF",
+        help = "Only include files added or modified since a git ref (e.g. main, HEAD~3)"
+    )]
+    pub changed_since: Option<String>,
+
+    #[cfg(feature = "git")]
+    #[arg(
+        long,
+        help = "With --changed-since, still show the full tree and limit only the contents"
+    )]
+    pub full_tree: bool,
+
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = FileUtils::parse_size,
+        help = "List but skip the contents of directories holding more than SIZE of included files (e.g. 50MB)"
+    )]
+    pub skip_content_over_dir_size: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Count the size and lines of excluded files in the summary"
+    )]
+    pub count_excluded: bool,
+
+    #[arg(
+        long,
+        help = "Produce byte-identical output across runs and platforms (no timings, dates or mtime ordering)"
+    )]
+    pub reproducible: bool,
+
+    #[arg(
+        long,
+        help = "Strip comments and blank lines from source files (transform.strip_comments)"
+    )]
+    pub strip_comments: bool,
+
+    #[arg(
+        long,
+        value_name = "OCTAL",
+        help = "Skip contents of files missing any of these permission bits (e.g. 040 for group-readable)"
+    )]
+    pub required_mode: Option<String>,
+
+    #[arg(
+        short = 'a',
+        long,
+        visible_alias = "all",
+        help = "Show all files, including ignored ones (with tree, hidden ones too)"
+    )]
     pub show_ignored: bool,
 
+    #[arg(
+        long,
+        help = "Include hidden files and directories (names starting with '.')"
+    )]
+    pub hidden: bool,
+
     #[arg(long, help = "Show binary files")]
     pub show_binary: bool,
 
-    #[arg(short = 'o', long, help = "Output filename")]
+    #[arg(
+        long,
+        help = "List binary files in the tree with their size, without their contents"
+    )]
+    pub list_binary: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How to handle symbolic links (loops are never followed)"
+    )]
+    pub symlinks: Option<SymlinkMode>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Classify files by extension and magic bytes (fast) or by decoding every file (full)"
+    )]
+    pub binary_detection: Option<BinaryDetection>,
+
+    #[arg(long, help = "Maximum path length before an entry is skipped")]
+    pub max_path_length: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Render a tree previously exported with --format json instead of scanning"
+    )]
+    pub from_json: Option<String>,
+
+    #[arg(long, value_enum, help = "Output format")]
+    pub format: Option<OutputFormat>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How to handle front matter in markdown files"
+    )]
+    pub front_matter: Option<FrontMatterMode>,
+
+    #[arg(
+        long,
+        help = "Include only module and top-level doc comments (Rust, Python, JS/TS) and README files"
+    )]
+    pub docs_only: bool,
+
+    #[arg(
+        long,
+        help = "Include local Git LFS objects up to this size instead of a placeholder"
+    )]
+    pub lfs_fetch_max_size: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Annotate the tree with file sizes, line counts and directory totals"
+    )]
+    pub stats: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        default_missing_value = "generalize",
+        help = "Export only the tree shape and sizes, with file names generalized or hashed"
+    )]
+    pub anonymize: Option<AnonymizeMode>,
+
+    #[arg(
+        long,
+        help = "Write only ASCII, escaping non-ASCII characters in names and contents"
+    )]
+    pub ascii: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Tree glyphs: unicode box drawing, ascii (|-- and `--) or indent only"
+    )]
+    pub tree_style: Option<TreeStyle>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Order of entries within each directory (directories always come first)"
+    )]
+    pub sort: Option<SortOrder>,
+
+    #[arg(long, help = "Reverse the --sort order, e.g. largest files first")]
+    pub reverse: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Order of file contents: tree order, largest first, newest first or by path"
+    )]
+    pub content_order: Option<ContentOrder>,
+
+    #[arg(
+        long,
+        help = "Record per-file read/transform/write times in JSON output and list the slowest files"
+    )]
+    pub timings: bool,
+
+    #[cfg(feature = "git")]
+    #[arg(
+        long,
+        help = "Prefix each file with the hash, author and date of its last commit"
+    )]
+    pub commit_info: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Also write the list of files with size, lines, sha256 and skip reason (JSON, or CSV for .csv)"
+    )]
+    pub manifest: Option<String>,
+
+    #[arg(
+        long,
+        help = "Detect SPDX identifiers and license texts and list them after the contents"
+    )]
+    pub licenses: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        help = "With audit, exit non-zero only when there are more than N findings"
+    )]
+    pub max_findings: usize,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Print a one-line run summary to stderr, add the full statistics (full), also append them to the output (append), or turn it off"
+    )]
+    pub summary: Option<SummaryMode>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Color the output of 'projct tree' (default: when stdout is a terminal and NO_COLOR is unset)"
+    )]
+    pub color: Option<ColorMode>,
+
+    #[arg(long, help = "Count tokens per file and append a summary")]
+    pub tokens: bool,
+
+    #[arg(long, value_enum, help = "Tokenizer used for token counts")]
+    pub tokenizer: Option<TokenizerKind>,
+
+    #[arg(
+        short = 'o',
+        long,
+        help = "Output filename ('-' for stdout, 'auto' for <project>-<date>.<ext>)"
+    )]
     pub output: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Split output into <name>.partN files of at most this many bytes"
+    )]
+    pub split_size: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Split output into <name>.partN files of at most this many tokens"
+    )]
+    pub split_tokens: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = FileUtils::parse_size,
+        help = "Abort once the output grows past SIZE (e.g. 100MB)"
+    )]
+    pub max_total_size: Option<u64>,
+
+    #[arg(
+        long,
+        help = "With --max-total-size, cut the output off with a marker instead of aborting"
+    )]
+    pub truncate_at_limit: bool,
+
+    #[arg(long, help = "Write output to stdout instead of a file")]
+    pub stdout: bool,
+
+    #[arg(
+        long,
+        help = "Copy output to the clipboard (also writes a file only if -o is given)"
+    )]
+    pub clipboard: bool,
+
+    #[arg(
+        long,
+        help = "Append to the output file instead of replacing it (e.g. after a prompt preamble)"
+    )]
+    pub append: bool,
+
+    #[arg(long, help = "Maximum number of files open at once (0 for no limit)")]
+    pub io_max_open: Option<usize>,
+
+    #[arg(long, help = "Maximum bytes read per second")]
+    pub io_bytes_per_sec: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Keep at most this much output in memory before spilling to a temporary file (0 for no limit)"
+    )]
+    pub io_max_buffer: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Size of the buffer in front of the output file or stdout (0 writes through immediately)"
+    )]
+    pub io_write_buffer: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Read file contents with this many threads (0 or 1 reads one file at a time)"
+    )]
+    pub io_read_workers: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Run the [hooks] pre and post commands from the config file"
+    )]
+    pub run_hooks: bool,
+
+    #[arg(
+        long,
+        help = "Show a desktop notification when the run finishes (needs notify-send or osascript)"
+    )]
+    pub notify: bool,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "POST the run summary as JSON to this URL when the run finishes (needs curl)"
+    )]
+    pub webhook: Option<String>,
+
+    #[arg(long, help = "With 'rerun', edit the recorded options before running")]
+    pub edit: bool,
+
     #[arg(
         short = 'c',
         long,
@@ -106,97 +914,612 @@ pub struct Args {
         help = "Config file path"
     )]
     pub config: String,
+
+    #[arg(
+        long,
+        help = "Apply the named [profile.<name>] table from the config file"
+    )]
+    pub profile: Option<String>,
+
+    #[arg(long, help = "Print the commented default configuration and exit")]
+    pub print_default_config: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::default_config()
+    }
 }
 
 impl Config {
-    pub fn new(config_path: &str, args: &Args, effective_path: String) -> Self {
-        let mut config = Self::load_config(config_path);
+    pub fn new(config_path: &str, args: &Args, paths: Vec<String>) -> Result<Self> {
+        let mut config = Self::load_config(config_path, args.profile.as_deref())?;
 
-        config.general.path = effective_path;
+        if let Some(path) = paths.first() {
+            config.general.path = path.clone();
+            config.general.paths = if paths.len() > 1 { paths } else { vec![] };
+        }
         if let Some(md) = args.max_depth {
             config.general.max_depth = Some(md);
         }
+        for pattern in args.exclude.iter().flat_map(|p| p.split('|')) {
+            let pattern = pattern.trim_end_matches('/');
+            if pattern.is_empty() {
+                continue;
+            }
+            config.filters.exclude_patterns.push(pattern.to_string());
+            config
+                .filters
+                .exclude_patterns
+                .push(format!("{}/", pattern));
+        }
+        if !args.deep.is_empty() {
+            config.filters.deep_patterns = args.deep.clone();
+        }
+        if let Some(depth) = args.overview_depth {
+            config.general.overview_depth = depth;
+        }
         if args.no_gitignore {
             config.general.use_gitignore = false;
         }
+        if args.no_default_excludes {
+            config.filters.default_excludes = false;
+        }
+        if args.no_summarize {
+            config.filters.summarize_patterns.clear();
+        }
+        config
+            .filters
+            .summarize_patterns
+            .extend(args.summarize.iter().cloned());
+        if args.include_config {
+            config.general.exclude_config = false;
+        }
+        if let Some(size) = args.skip_content_over_dir_size {
+            config.general.skip_content_over_dir_size = Some(size);
+        }
+        if args.count_excluded {
+            config.general.count_excluded = true;
+        }
+        if args.reproducible {
+            config.general.reproducible = true;
+        }
+        if args.strip_comments {
+            config.transform.strip_comments = true;
+        }
+        if !args.extra_ignore_file.is_empty() {
+            config.general.extra_ignore_files = args.extra_ignore_file.clone();
+        }
+        if !args.exclude_owner.is_empty() {
+            config.filters.exclude_owners = args.exclude_owner.clone();
+        }
+        if !args.include_group.is_empty() {
+            config.filters.include_groups = args.include_group.clone();
+        }
+        if !args.exclude_group.is_empty() {
+            config.filters.exclude_groups = args.exclude_group.clone();
+        }
+        if let Some(mode) = &args.required_mode {
+            config.filters.required_mode = Some(mode.clone());
+        }
         if args.show_ignored {
             config.general.show_ignored = true;
         }
+        if args.hidden {
+            config.general.hidden = true;
+        }
         if args.show_binary {
             config.general.show_binary = true;
         }
+        if args.list_binary {
+            config.general.list_binary = true;
+        }
+        if let Some(mpl) = args.max_path_length {
+            config.general.max_path_length = mpl;
+        }
+        if let Some(mode) = args.symlinks {
+            config.general.symlinks = mode;
+        }
+        if let Some(detection) = args.binary_detection {
+            config.general.binary_detection = detection;
+        }
+        if let Some(fj) = &args.from_json {
+            config.general.from_json = Some(fj.clone());
+        }
         if let Some(o) = &args.output {
             config.output.filename = o.clone();
         }
+        if args.stdout {
+            config.output.filename = "-".to_string();
+        }
+        if args.append {
+            config.output.append = true;
+        }
+        if args.clipboard {
+            config.output.clipboard = true;
+            if args.output.is_none() && !args.stdout {
+                config.output.filename = String::new();
+            }
+        }
         if args.max_size != 100000 {
             config.output.max_file_size = args.max_size;
         }
+        if let Some(truncate_lines) = args.truncate_lines {
+            config.output.truncate_lines = Some(truncate_lines);
+        }
         if args.line_numbers {
             config.output.show_line_numbers = true;
         }
+        if let Some(f) = args.format {
+            if args.output.is_none()
+                && config.output.filename == config.output.format.default_filename()
+            {
+                config.output.filename = f.default_filename();
+            }
+            config.output.format = f;
+        }
+        if args.docs_only {
+            config.output.docs_only = true;
+        }
+        if let Some(fm) = args.front_matter {
+            config.output.front_matter = fm;
+        }
+        if let Some(lfs) = args.lfs_fetch_max_size {
+            config.output.lfs_fetch_max_size = lfs;
+        }
+        if args.ascii {
+            config.output.ascii = true;
+        }
+        if let Some(style) = args.tree_style {
+            config.output.tree_style = style;
+        }
+        if let Some(sort) = args.sort {
+            config.output.sort = sort;
+        }
+        if args.reverse {
+            config.output.reverse = true;
+        }
+        if let Some(order) = args.content_order {
+            config.output.content_order = order;
+        }
+        if args.stats {
+            config.output.annotate = true;
+        }
+        if let Some(mode) = args.anonymize {
+            config.output.anonymize = mode;
+        }
+        if let Some(size) = args.split_size {
+            config.output.split_size = Some(size);
+        }
+        if let Some(tokens) = args.split_tokens {
+            config.output.split_tokens = Some(tokens);
+        }
+        if let Some(size) = args.max_total_size {
+            config.output.max_total_size = Some(size);
+        }
+        if args.truncate_at_limit {
+            config.output.truncate_at_limit = true;
+        }
+        if args.timings {
+            config.output.timings = true;
+        }
+        if let Some(path) = &args.manifest {
+            config.output.manifest = Some(path.clone());
+        }
+        if args.licenses {
+            config.output.licenses = true;
+        }
+        if let Some(mode) = args.summary {
+            config.output.summary = mode;
+        }
+        if args.tokens {
+            config.output.tokens = true;
+        }
+        if let Some(t) = args.tokenizer {
+            config.output.tokenizer = t;
+        }
+        if args.run_hooks {
+            config.hooks.run = true;
+        }
+        if args.notify {
+            config.notifications.desktop = true;
+        }
+        if let Some(url) = &args.webhook {
+            config.notifications.webhook = Some(url.clone());
+        }
+        if let Some(mo) = args.io_max_open {
+            config.io.max_open_files = Some(mo);
+        }
+        if let Some(bps) = args.io_bytes_per_sec {
+            config.io.bytes_per_sec = Some(bps);
+        }
+        if let Some(size) = args.io_max_buffer {
+            config.io.max_buffer_size = size;
+        }
+        if let Some(size) = args.io_write_buffer {
+            config.io.write_buffer_size = size;
+        }
+        if let Some(workers) = args.io_read_workers {
+            config.io.read_workers = workers;
+        }
+        config.apply_git_args(args);
+        PermissionFilter::new(&config.filters).map_err(Error::Usage)?;
+        config.check_groups().map_err(Error::Usage)?;
+        config.check_git().map_err(Error::Usage)?;
 
-        config
+        Ok(config)
     }
 
-    fn load_config(config_path: &str) -> Self {
-        let default_config = Self::default_config();
+    #[cfg(feature = "git")]
+    fn apply_git_args(&mut self, args: &Args) {
+        if args.git_tracked {
+            self.general.git_tracked = true;
+        }
+        if let Some(git_ref) = &args.changed_since {
+            self.general.changed_since = Some(git_ref.clone());
+        }
+        if args.full_tree {
+            self.general.full_tree = true;
+        }
+        if args.commit_info {
+            self.output.commit_info = true;
+        }
+    }
 
-        if !Path::new(config_path).exists() {
-            return default_config;
+    #[cfg(not(feature = "git"))]
+    fn apply_git_args(&mut self, _args: &Args) {}
+
+    #[cfg(feature = "git")]
+    fn check_git(&self) -> std::result::Result<(), String> {
+        Ok(())
+    }
+
+    #[cfg(not(feature = "git"))]
+    fn check_git(&self) -> std::result::Result<(), String> {
+        let used = [
+            ("general.git_tracked", self.general.git_tracked),
+            (
+                "general.changed_since",
+                self.general.changed_since.is_some(),
+            ),
+            ("output.commit_info", self.output.commit_info),
+        ];
+        match used.iter().find(|(_, set)| *set) {
+            Some((key, _)) => Err(format!("{} needs projct built with the git feature", key)),
+            None => Ok(()),
         }
+    }
 
-        let mut file = match File::open(config_path) {
-            Ok(f) => f,
-            Err(e) => {
-                println!("Cannot load config: {}. Using defaults.", e);
-                return default_config;
+    fn check_groups(&self) -> std::result::Result<(), String> {
+        let used = self
+            .filters
+            .include_groups
+            .iter()
+            .chain(&self.filters.exclude_groups)
+            .chain(&self.output.group_order);
+        for name in used {
+            if !self.groups.contains_key(name) {
+                let defined: Vec<&str> = self.groups.keys().map(String::as_str).collect();
+                return Err(if defined.is_empty() {
+                    format!("unknown group `{}`: no [groups] defined", name)
+                } else {
+                    format!("unknown group `{}` (defined: {})", name, defined.join(", "))
+                });
             }
-        };
+        }
+        Ok(())
+    }
 
-        let mut contents = String::new();
-        if file.read_to_string(&mut contents).is_err() {
-            println!("Cannot read config. Using defaults.");
-            return default_config;
+    pub fn group_patterns(&self, name: &str) -> &[String] {
+        self.groups.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn expand_groups(&mut self) {
+        for name in std::mem::take(&mut self.filters.include_groups) {
+            let patterns = self.group_patterns(&name).to_vec();
+            self.filters.include_patterns.extend(patterns);
+        }
+        for name in std::mem::take(&mut self.filters.exclude_groups) {
+            let patterns = self.group_patterns(&name).to_vec();
+            self.filters.exclude_patterns.extend(patterns);
+        }
+    }
+
+    pub fn pin_reproducible(&mut self) {
+        if !self.general.reproducible {
+            return;
+        }
+        self.output.timings = false;
+        if self.output.sort == SortOrder::Mtime {
+            self.output.sort = SortOrder::Name;
+        }
+        if self.output.content_order == ContentOrder::Mtime {
+            self.output.content_order = ContentOrder::Tree;
         }
+    }
 
-        let loaded_raw: RawConfig = match toml::from_str(&contents) {
-            Ok(c) => c,
-            Err(e) => {
-                println!("Cannot parse config: {}. Using defaults.", e);
-                return default_config;
+    pub fn roots(&self) -> Vec<String> {
+        if self.general.paths.is_empty() {
+            vec![self.general.path.clone()]
+        } else {
+            self.general.paths.clone()
+        }
+    }
+
+    fn merge_table(base: &mut toml::Table, overlay: &toml::Table) {
+        for (key, value) in overlay {
+            match (base.get_mut(key), value) {
+                (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                    Self::merge_table(base_table, overlay_table);
+                }
+                _ => {
+                    base.insert(key.clone(), value.clone());
+                }
             }
+        }
+    }
+
+    fn load_config(config_path: &str, profile: Option<&str>) -> Result<Self> {
+        let default_config = Self::default_config();
+
+        let config_error = |message: String| Error::Config {
+            path: config_path.into(),
+            message,
         };
+        if !Path::new(config_path).exists() {
+            return match profile {
+                Some(name) => Err(config_error(format!(
+                    "profile `{}` requested but the config file does not exist",
+                    name
+                ))),
+                None => Ok(default_config),
+            };
+        }
+
+        let mut contents = String::new();
+        File::open(config_path)
+            .and_then(|mut f| f.read_to_string(&mut contents))
+            .map_err(|e| config_error(e.to_string()))?;
+
+        let loaded_raw: RawConfig = match profile {
+            None => toml::from_str(&contents),
+            Some(name) => {
+                let mut table: toml::Table = toml::from_str(&contents)
+                    .map_err(|e| config_error(e.to_string().trim_end().to_string()))?;
+                let profiles = table.remove("profile");
+                let selected = profiles
+                    .as_ref()
+                    .and_then(|p| p.get(name))
+                    .and_then(toml::Value::as_table)
+                    .ok_or_else(|| {
+                        let available: Vec<&str> = profiles
+                            .iter()
+                            .filter_map(toml::Value::as_table)
+                            .flat_map(|p| p.keys().map(String::as_str))
+                            .collect();
+                        config_error(if available.is_empty() {
+                            format!("no profile `{}`: no [profile.<name>] tables defined", name)
+                        } else {
+                            format!(
+                                "no profile `{}` (available: {})",
+                                name,
+                                available.join(", ")
+                            )
+                        })
+                    })?;
+                Self::merge_table(&mut table, selected);
+                table.try_into()
+            }
+        }
+        .map_err(|e| config_error(e.to_string().trim_end().to_string()))?;
 
-        let loaded_general = loaded_raw.general.unwrap_or_default();
-        let loaded_output = loaded_raw.output.unwrap_or_default();
+        let mut loaded_general = loaded_raw.general.unwrap_or_default();
+        let mut loaded_output = loaded_raw.output.unwrap_or_default();
+        let config_dir = Path::new(config_path).parent().unwrap_or(Path::new(""));
+        loaded_general.path = loaded_general
+            .path
+            .map(|p| Self::resolve_config_relative(config_dir, p));
+        loaded_general.from_json = loaded_general
+            .from_json
+            .map(|p| Self::resolve_config_relative(config_dir, p));
+        loaded_general.paths = loaded_general.paths.map(|paths| {
+            paths
+                .into_iter()
+                .map(|p| Self::resolve_config_relative(config_dir, p))
+                .collect()
+        });
+        loaded_output.filename = loaded_output
+            .filename
+            .map(|f| Self::resolve_config_relative(config_dir, f));
+        loaded_output.manifest = loaded_output
+            .manifest
+            .map(|m| Self::resolve_config_relative(config_dir, m));
         let loaded_filters = loaded_raw.filters.unwrap_or_default();
+        let loaded_io = loaded_raw.io.unwrap_or_default();
+        let loaded_sampling = loaded_raw.sampling.unwrap_or_default();
+        let loaded_redact = loaded_raw.redact.unwrap_or_default();
+        let loaded_transform = loaded_raw.transform.unwrap_or_default();
+        let loaded_hooks = loaded_raw.hooks.unwrap_or_default();
+        let loaded_notifications = loaded_raw.notifications.unwrap_or_default();
+        for pattern in loaded_redact.patterns.iter().flatten() {
+            Regex::new(pattern).map_err(|e| {
+                config_error(format!("invalid redact pattern `{}`: {}", pattern, e))
+            })?;
+        }
 
-        Config {
+        Ok(Config {
             general: General {
                 path: loaded_general.path.unwrap_or(default_config.general.path),
                 max_depth: loaded_general
                     .max_depth
                     .or(default_config.general.max_depth),
+                overview_depth: loaded_general
+                    .overview_depth
+                    .unwrap_or(default_config.general.overview_depth),
                 use_gitignore: loaded_general
                     .use_gitignore
                     .unwrap_or(default_config.general.use_gitignore),
                 show_ignored: loaded_general
                     .show_ignored
                     .unwrap_or(default_config.general.show_ignored),
+                hidden: loaded_general
+                    .hidden
+                    .unwrap_or(default_config.general.hidden),
                 show_binary: loaded_general
                     .show_binary
                     .unwrap_or(default_config.general.show_binary),
+                list_binary: loaded_general
+                    .list_binary
+                    .unwrap_or(default_config.general.list_binary),
+                max_path_length: loaded_general
+                    .max_path_length
+                    .unwrap_or(default_config.general.max_path_length),
+                from_json: loaded_general
+                    .from_json
+                    .or(default_config.general.from_json),
+                symlinks: loaded_general
+                    .symlinks
+                    .unwrap_or(default_config.general.symlinks),
+                binary_detection: loaded_general
+                    .binary_detection
+                    .unwrap_or(default_config.general.binary_detection),
+                extra_ignore_files: loaded_general
+                    .extra_ignore_files
+                    .unwrap_or(default_config.general.extra_ignore_files),
+                git_tracked: loaded_general
+                    .git_tracked
+                    .unwrap_or(default_config.general.git_tracked),
+                changed_since: loaded_general
+                    .changed_since
+                    .or(default_config.general.changed_since),
+                full_tree: loaded_general
+                    .full_tree
+                    .unwrap_or(default_config.general.full_tree),
+                skip_content_over_dir_size: loaded_general
+                    .skip_content_over_dir_size
+                    .or(default_config.general.skip_content_over_dir_size),
+                count_excluded: loaded_general
+                    .count_excluded
+                    .unwrap_or(default_config.general.count_excluded),
+                exclude_config: loaded_general
+                    .exclude_config
+                    .unwrap_or(default_config.general.exclude_config),
+                reproducible: loaded_general
+                    .reproducible
+                    .unwrap_or(default_config.general.reproducible),
+                paths: loaded_general.paths.unwrap_or(default_config.general.paths),
             },
             output: Output {
-                filename: loaded_output
-                    .filename
-                    .unwrap_or(default_config.output.filename),
+                filename: loaded_output.filename.unwrap_or_else(|| {
+                    loaded_output
+                        .format
+                        .unwrap_or(default_config.output.format)
+                        .default_filename()
+                }),
                 max_file_size: loaded_output
                     .max_file_size
                     .unwrap_or(default_config.output.max_file_size),
+                truncate_lines: loaded_output
+                    .truncate_lines
+                    .or(default_config.output.truncate_lines),
                 show_line_numbers: loaded_output
                     .show_line_numbers
                     .unwrap_or(default_config.output.show_line_numbers),
+                name_template: loaded_output
+                    .name_template
+                    .unwrap_or(default_config.output.name_template),
+                header: loaded_output.header.or(default_config.output.header),
+                footer: loaded_output.footer.or(default_config.output.footer),
+                append: loaded_output.append.unwrap_or(default_config.output.append),
+                format: loaded_output.format.unwrap_or(default_config.output.format),
+                front_matter: loaded_output
+                    .front_matter
+                    .unwrap_or(default_config.output.front_matter),
+                docs_only: loaded_output
+                    .docs_only
+                    .unwrap_or(default_config.output.docs_only),
+                lfs_fetch_max_size: loaded_output
+                    .lfs_fetch_max_size
+                    .unwrap_or(default_config.output.lfs_fetch_max_size),
+                tokens: loaded_output.tokens.unwrap_or(default_config.output.tokens),
+                tokenizer: loaded_output
+                    .tokenizer
+                    .unwrap_or(default_config.output.tokenizer),
+                clipboard: loaded_output
+                    .clipboard
+                    .unwrap_or(default_config.output.clipboard),
+                annotate: loaded_output
+                    .annotate
+                    .unwrap_or(default_config.output.annotate),
+                split_size: loaded_output
+                    .split_size
+                    .or(default_config.output.split_size),
+                split_tokens: loaded_output
+                    .split_tokens
+                    .or(default_config.output.split_tokens),
+                max_total_size: loaded_output
+                    .max_total_size
+                    .or(default_config.output.max_total_size),
+                truncate_at_limit: loaded_output
+                    .truncate_at_limit
+                    .unwrap_or(default_config.output.truncate_at_limit),
+                timings: loaded_output
+                    .timings
+                    .unwrap_or(default_config.output.timings),
+                commit_info: loaded_output
+                    .commit_info
+                    .unwrap_or(default_config.output.commit_info),
+                manifest: loaded_output.manifest.or(default_config.output.manifest),
+                licenses: loaded_output
+                    .licenses
+                    .unwrap_or(default_config.output.licenses),
+                summary: loaded_output
+                    .summary
+                    .unwrap_or(default_config.output.summary),
+                ascii: loaded_output.ascii.unwrap_or(default_config.output.ascii),
+                tree_style: loaded_output
+                    .tree_style
+                    .unwrap_or(default_config.output.tree_style),
+                sort: loaded_output.sort.unwrap_or(default_config.output.sort),
+                reverse: loaded_output
+                    .reverse
+                    .unwrap_or(default_config.output.reverse),
+                content_order: loaded_output
+                    .content_order
+                    .unwrap_or(default_config.output.content_order),
+                group_order: loaded_output
+                    .group_order
+                    .unwrap_or(default_config.output.group_order),
+                entropy_threshold: loaded_output
+                    .entropy_threshold
+                    .unwrap_or(default_config.output.entropy_threshold),
+                anonymize: loaded_output
+                    .anonymize
+                    .unwrap_or(default_config.output.anonymize),
+                language_map: loaded_output
+                    .language_map
+                    .map(|m| {
+                        m.into_iter()
+                            .map(|(k, v)| (k.trim_start_matches('.').to_lowercase(), v))
+                            .collect()
+                    })
+                    .unwrap_or(default_config.output.language_map),
+                path_aliases: loaded_output
+                    .path_aliases
+                    .map(|m| {
+                        m.into_iter()
+                            .map(|(k, v)| (k.replace('\\', "/").trim_matches('/').to_string(), v))
+                            .collect()
+                    })
+                    .unwrap_or(default_config.output.path_aliases),
+                max_size_by_ext: loaded_output
+                    .max_size_by_ext
+                    .map(|m| {
+                        m.into_iter()
+                            .map(|(k, v)| (k.trim_start_matches('.').to_lowercase(), v))
+                            .collect()
+                    })
+                    .unwrap_or(default_config.output.max_size_by_ext),
             },
             filters: Filters {
                 include_patterns: loaded_filters
@@ -205,8 +1528,91 @@ impl Config {
                 exclude_patterns: loaded_filters
                     .exclude_patterns
                     .unwrap_or(default_config.filters.exclude_patterns),
+                deep_patterns: loaded_filters
+                    .deep_patterns
+                    .unwrap_or(default_config.filters.deep_patterns),
+                default_excludes: loaded_filters
+                    .default_excludes
+                    .unwrap_or(default_config.filters.default_excludes),
+                summarize_patterns: loaded_filters
+                    .summarize_patterns
+                    .unwrap_or(default_config.filters.summarize_patterns),
+                exclude_owners: loaded_filters
+                    .exclude_owners
+                    .unwrap_or(default_config.filters.exclude_owners),
+                required_mode: loaded_filters
+                    .required_mode
+                    .or(default_config.filters.required_mode),
+                include_groups: loaded_filters
+                    .include_groups
+                    .unwrap_or(default_config.filters.include_groups),
+                exclude_groups: loaded_filters
+                    .exclude_groups
+                    .unwrap_or(default_config.filters.exclude_groups),
+            },
+            io: Io {
+                max_open_files: loaded_io
+                    .max_open_files
+                    .or(default_config.io.max_open_files),
+                bytes_per_sec: loaded_io.bytes_per_sec.or(default_config.io.bytes_per_sec),
+                max_buffer_size: loaded_io
+                    .max_buffer_size
+                    .unwrap_or(default_config.io.max_buffer_size),
+                write_buffer_size: loaded_io
+                    .write_buffer_size
+                    .unwrap_or(default_config.io.write_buffer_size),
+                read_workers: loaded_io
+                    .read_workers
+                    .unwrap_or(default_config.io.read_workers),
+            },
+            sampling: Sampling {
+                patterns: loaded_sampling
+                    .patterns
+                    .unwrap_or(default_config.sampling.patterns),
+                count: loaded_sampling
+                    .count
+                    .unwrap_or(default_config.sampling.count),
+                seed: loaded_sampling.seed.unwrap_or(default_config.sampling.seed),
+            },
+            redact: Redact {
+                enabled: loaded_redact
+                    .enabled
+                    .unwrap_or(default_config.redact.enabled),
+                patterns: loaded_redact
+                    .patterns
+                    .unwrap_or(default_config.redact.patterns),
             },
+            transform: Transform {
+                strip_comments: loaded_transform
+                    .strip_comments
+                    .unwrap_or(default_config.transform.strip_comments),
+            },
+            hooks: Hooks {
+                pre: loaded_hooks.pre.or(default_config.hooks.pre),
+                post: loaded_hooks.post.or(default_config.hooks.post),
+                run: false,
+            },
+            notifications: Notifications {
+                desktop: loaded_notifications
+                    .desktop
+                    .unwrap_or(default_config.notifications.desktop),
+                webhook: loaded_notifications
+                    .webhook
+                    .or(default_config.notifications.webhook),
+            },
+            groups: loaded_raw.groups.unwrap_or(default_config.groups),
+            source: Some(PathBuf::from(config_path)),
+        })
+    }
+
+    fn resolve_config_relative(config_dir: &Path, value: String) -> String {
+        if matches!(value.as_str(), "" | "-" | "auto") || Path::new(&value).is_absolute() {
+            return value;
+        }
+        if config_dir.as_os_str().is_empty() {
+            return value;
         }
+        config_dir.join(value).to_string_lossy().to_string()
     }
 
     fn default_config() -> Self {
@@ -214,33 +1620,221 @@ impl Config {
             general: General {
                 path: ".".to_string(),
                 max_depth: None,
+                overview_depth: 2,
                 use_gitignore: true,
                 show_ignored: false,
+                hidden: false,
                 show_binary: false,
+                list_binary: false,
+                max_path_length: DEFAULT_MAX_PATH_LENGTH,
+                from_json: None,
+                symlinks: SymlinkMode::Follow,
+                binary_detection: BinaryDetection::Fast,
+                extra_ignore_files: vec![],
+                git_tracked: false,
+                changed_since: None,
+                full_tree: false,
+                skip_content_over_dir_size: None,
+                count_excluded: false,
+                exclude_config: true,
+                reproducible: false,
+                paths: vec![],
             },
             output: Output {
-                filename: "output.txt".to_string(),
+                filename: OutputFormat::Text.default_filename(),
                 max_file_size: 100000,
+                truncate_lines: None,
                 show_line_numbers: false,
+                name_template: DEFAULT_NAME_TEMPLATE.to_string(),
+                header: None,
+                footer: None,
+                append: false,
+                format: OutputFormat::Text,
+                front_matter: FrontMatterMode::Keep,
+                docs_only: false,
+                lfs_fetch_max_size: 0,
+                tokens: false,
+                tokenizer: TokenizerKind::Cl100k,
+                clipboard: false,
+                annotate: false,
+                split_size: None,
+                split_tokens: None,
+                max_total_size: None,
+                truncate_at_limit: false,
+                timings: false,
+                commit_info: false,
+                manifest: None,
+                licenses: false,
+                summary: SummaryMode::Print,
+                ascii: false,
+                tree_style: TreeStyle::Unicode,
+                sort: SortOrder::Name,
+                reverse: false,
+                content_order: ContentOrder::Tree,
+                group_order: vec![],
+                entropy_threshold: 5.9,
+                anonymize: AnonymizeMode::Off,
+                language_map: BTreeMap::new(),
+                path_aliases: BTreeMap::new(),
+                max_size_by_ext: BTreeMap::new(),
             },
             filters: Filters {
                 include_patterns: vec![],
                 exclude_patterns: vec![],
+                deep_patterns: vec![],
+                default_excludes: true,
+                summarize_patterns: filters::DEFAULT_SUMMARIZE_PATTERNS
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect(),
+                exclude_owners: vec![],
+                required_mode: None,
+                include_groups: vec![],
+                exclude_groups: vec![],
+            },
+            io: Io {
+                max_open_files: Some(256),
+                bytes_per_sec: None,
+                max_buffer_size: 64 * 1024 * 1024,
+                write_buffer_size: 64 * 1024,
+                read_workers: 4,
             },
+            sampling: Sampling {
+                patterns: vec![],
+                count: 5,
+                seed: 0,
+            },
+            redact: Redact {
+                enabled: true,
+                patterns: vec![],
+            },
+            transform: Transform {
+                strip_comments: false,
+            },
+            hooks: Hooks {
+                pre: None,
+                post: None,
+                run: false,
+            },
+            notifications: Notifications {
+                desktop: false,
+                webhook: None,
+            },
+            groups: BTreeMap::new(),
+            source: None,
         }
     }
 
-    pub fn create_config(config_path: &str) {
-        let mut file = match File::create(config_path) {
-            Ok(f) => f,
+    pub fn save_last_run(&self) {
+        let mut config = self.clone();
+        config.general.path = Self::absolute_path(&config.general.path);
+        config.general.paths = config
+            .general
+            .paths
+            .iter()
+            .map(|p| Self::absolute_path(p))
+            .collect();
+        config.general.from_json = config.general.from_json.as_deref().map(Self::absolute_path);
+        config.output.manifest = config.output.manifest.as_deref().map(Self::absolute_path);
+        if !matches!(config.output.filename.as_str(), "" | "-" | "auto") {
+            config.output.filename = Self::absolute_path(&config.output.filename);
+        }
+        let contents = match toml::to_string(&config) {
+            Ok(c) => c,
             Err(e) => {
-                println!("Error creating config: {}", e);
+                eprintln!("Cannot record last run: {}", e);
                 return;
             }
         };
-        if file.write_all(CONFIG.as_bytes()).is_err() {
-            println!("Error writing config.");
-        } else {
+        let last_run = Path::new(LAST_RUN_PATH);
+        if let Some(dir) = last_run.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                eprintln!("Cannot record last run: {}", e);
+                return;
+            }
+        }
+        if let Err(e) = fs::write(last_run, contents) {
+            eprintln!("Cannot record last run: {}", e);
+        }
+    }
+
+    fn absolute_path(value: &str) -> String {
+        std::path::absolute(value)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| value.to_string())
+    }
+
+    pub fn edit_file(path: &str) -> Result<()> {
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| {
+                if cfg!(windows) {
+                    "notepad".to_string()
+                } else {
+                    "vi".to_string()
+                }
+            });
+        let mut parts = editor.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| Error::Editor("No editor configured".to_string()))?;
+        let status = Command::new(program)
+            .args(parts)
+            .arg(path)
+            .status()
+            .map_err(|e| Error::Editor(format!("Cannot start editor '{}': {}", editor, e)))?;
+        if !status.success() {
+            return Err(Error::Editor(format!(
+                "Editor '{}' exited with {}",
+                editor, status
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn create_config(config_path: &str) -> Result<()> {
+        File::create(config_path)
+            .and_then(|mut f| f.write_all(config_template::default_config_toml().as_bytes()))
+            .map_err(|source| Error::CreateConfig {
+                path: config_path.into(),
+                source,
+            })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawSize {
+    Bytes(u64),
+    Text(String),
+}
+
+impl RawSize {
+    fn bytes<E: serde::de::Error>(self) -> std::result::Result<u64, E> {
+        match self {
+            RawSize::Bytes(bytes) => Ok(bytes),
+            RawSize::Text(text) => FileUtils::parse_size(&text).map_err(E::custom),
         }
     }
 }
+
+fn deserialize_size<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<u64>, D::Error> {
+    Option::<RawSize>::deserialize(deserializer)?
+        .map(RawSize::bytes)
+        .transpose()
+}
+
+fn deserialize_size_map<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<BTreeMap<String, u64>>, D::Error> {
+    Option::<BTreeMap<String, RawSize>>::deserialize(deserializer)?
+        .map(|sizes| {
+            sizes
+                .into_iter()
+                .map(|(ext, size)| Ok((ext, size.bytes()?)))
+                .collect()
+        })
+        .transpose()
+}
@@ -0,0 +1,491 @@
+use std::fmt::Write as _;
+
+use crate::config::Config;
+
+pub struct FieldDoc {
+    pub section: &'static str,
+    pub key: &'static str,
+    pub doc: &'static str,
+    pub example: Option<&'static str>,
+    pub table: bool,
+}
+
+const fn field(section: &'static str, key: &'static str, doc: &'static str) -> FieldDoc {
+    FieldDoc {
+        section,
+        key,
+        doc,
+        example: None,
+        table: false,
+    }
+}
+
+const fn optional(
+    section: &'static str,
+    key: &'static str,
+    doc: &'static str,
+    example: &'static str,
+) -> FieldDoc {
+    FieldDoc {
+        section,
+        key,
+        doc,
+        example: Some(example),
+        table: false,
+    }
+}
+
+const fn table(
+    section: &'static str,
+    key: &'static str,
+    doc: &'static str,
+    example: &'static str,
+) -> FieldDoc {
+    FieldDoc {
+        section,
+        key,
+        doc,
+        example: Some(example),
+        table: true,
+    }
+}
+
+const SECTIONS: &[&str] = &[
+    "general",
+    "output",
+    "filters",
+    "io",
+    "sampling",
+    "redact",
+    "transform",
+    "hooks",
+    "notifications",
+];
+
+pub const FIELD_DOCS: &[FieldDoc] = &[
+    field("general", "path", "Directory (or file) to scan"),
+    optional(
+        "general",
+        "paths",
+        "Scan several directories into one output instead of `path`",
+        r#"["frontend", "backend"]"#,
+    ),
+    optional("general", "max_depth", "Maximum depth to traverse", "3"),
+    field(
+        "general",
+        "overview_depth",
+        "Levels of the tree shown outside filters.deep_patterns",
+    ),
+    field(
+        "general",
+        "use_gitignore",
+        "Honor .gitignore and .projctignore files",
+    ),
+    field("general", "show_ignored", "Show ignored files in the tree"),
+    field(
+        "general",
+        "hidden",
+        "Include dotfiles and dot-directories (.git is always skipped)",
+    ),
+    field("general", "show_binary", "Show binary files in the tree"),
+    field(
+        "general",
+        "list_binary",
+        "List binary files with their size, without their contents",
+    ),
+    field(
+        "general",
+        "max_path_length",
+        "Maximum path length before an entry is skipped",
+    ),
+    optional(
+        "general",
+        "from_json",
+        "Render a tree previously exported with format = \"json\" instead of scanning",
+        r#""tree.json""#,
+    ),
+    field(
+        "general",
+        "symlinks",
+        "\"follow\", \"skip\" or \"show-as-link\" (loops are never followed)",
+    ),
+    field(
+        "general",
+        "binary_detection",
+        "\"fast\" (extension and magic bytes) or \"full\" (decode every file)",
+    ),
+    field(
+        "general",
+        "extra_ignore_files",
+        "More ignore files read like .gitignore, e.g. [\".dockerignore\"]",
+    ),
+    field(
+        "general",
+        "git_tracked",
+        "Only include files tracked by git",
+    ),
+    optional(
+        "general",
+        "changed_since",
+        "Only include files added or modified since this git ref",
+        r#""main""#,
+    ),
+    field(
+        "general",
+        "full_tree",
+        "With changed_since, still show the full tree",
+    ),
+    optional(
+        "general",
+        "skip_content_over_dir_size",
+        "List but skip the contents of directories holding more than this of included files",
+        "\"50MB\"",
+    ),
+    field(
+        "general",
+        "count_excluded",
+        "Count the size and lines of ignored and filtered files in the summary",
+    ),
+    field(
+        "general",
+        "exclude_config",
+        "Leave this config file out of the output when it lies inside the scanned directory",
+    ),
+    field(
+        "general",
+        "reproducible",
+        "Byte-identical output across runs and platforms: no timings, no dates, no mtime ordering",
+    ),
+    field(
+        "output",
+        "filename",
+        "Output file (\"-\" for stdout, \"auto\" for <project>-<date>.<ext>)",
+    ),
+    field(
+        "output",
+        "max_file_size",
+        "Skip contents of larger files (bytes)",
+    ),
+    optional(
+        "output",
+        "truncate_lines",
+        "Show the first and last lines of files over max_file_size instead of skipping them",
+        "{ head = 200, tail = 50 }",
+    ),
+    field(
+        "output",
+        "show_line_numbers",
+        "Prefix content lines with numbers",
+    ),
+    field(
+        "output",
+        "name_template",
+        "File names for multiple roots and split parts",
+    ),
+    optional(
+        "output",
+        "header",
+        "Text written before the tree; {root}, {date} and {file_count} are filled in (not for json)",
+        "\"Review the code of {root} ({file_count} files) below.\"",
+    ),
+    optional(
+        "output",
+        "footer",
+        "Text written after everything else, with the same placeholders as header",
+        "\"End of {root} as of {date}.\"",
+    ),
+    field(
+        "output",
+        "append",
+        "Append to the output file instead of replacing it",
+    ),
+    field(
+        "output",
+        "format",
+        "\"text\", \"markdown\", \"json\" or \"xml-docs\" (<document> tags per file)",
+    ),
+    field(
+        "output",
+        "front_matter",
+        "Markdown front matter: \"keep\", \"strip\" or \"summarize\"",
+    ),
+    field(
+        "output",
+        "docs_only",
+        "Write only module and top-level doc comments of source files, plus README files",
+    ),
+    field(
+        "output",
+        "lfs_fetch_max_size",
+        "Include local Git LFS objects up to this size instead of a placeholder",
+    ),
+    field(
+        "output",
+        "tokens",
+        "Count tokens per file and append a summary",
+    ),
+    field("output", "tokenizer", "\"cl100k\" or \"heuristic\""),
+    field("output", "clipboard", "Copy the output to the clipboard"),
+    field(
+        "output",
+        "annotate",
+        "Annotate the tree with sizes, line counts and directory totals",
+    ),
+    optional(
+        "output",
+        "split_size",
+        "Split the output into parts of at most this many bytes",
+        "1000000",
+    ),
+    optional(
+        "output",
+        "split_tokens",
+        "Split the output into parts of at most this many tokens",
+        "100000",
+    ),
+    optional(
+        "output",
+        "max_total_size",
+        "Abort once the output grows past this size",
+        "\"100MB\"",
+    ),
+    field(
+        "output",
+        "truncate_at_limit",
+        "With max_total_size, cut the output off with a marker instead of aborting",
+    ),
+    field(
+        "output",
+        "timings",
+        "Record per-file read/transform/write times in JSON output",
+    ),
+    field(
+        "output",
+        "commit_info",
+        "Prefix each file with the hash, author and date of its last commit",
+    ),
+    optional(
+        "output",
+        "manifest",
+        "Also write the file list with size, lines, sha256 and skip reason (JSON, or CSV for .csv)",
+        r#""manifest.json""#,
+    ),
+    field(
+        "output",
+        "licenses",
+        "List detected licenses after the contents",
+    ),
+    field(
+        "output",
+        "summary",
+        "\"print\" (one line on stderr), \"full\" (statistics on stderr), \"append\" (statistics in the output) or \"off\"",
+    ),
+    field(
+        "output",
+        "ascii",
+        "Write only ASCII, escaping other characters",
+    ),
+    field(
+        "output",
+        "tree_style",
+        "\"unicode\", \"ascii\" or \"indent\"",
+    ),
+    field(
+        "output",
+        "sort",
+        "Entry order within directories: \"name\", \"size\", \"mtime\" or \"extension\"",
+    ),
+    field("output", "reverse", "Reverse the sort order"),
+    field(
+        "output",
+        "content_order",
+        "Contents in \"tree\" order, \"size\" (largest first), \"mtime\" (newest first) or \"alpha\" (by path)",
+    ),
+    field(
+        "output",
+        "group_order",
+        "Put the contents of these [groups] first, in this order",
+    ),
+    field(
+        "output",
+        "entropy_threshold",
+        "Skip contents above this many bits of entropy per byte (0 disables)",
+    ),
+    field(
+        "output",
+        "anonymize",
+        "\"off\", \"generalize\" or \"hash\": export only the tree shape",
+    ),
+    table(
+        "output",
+        "language_map",
+        "Markdown fence language by extension or file name",
+        "svelte = \"svelte\"",
+    ),
+    table(
+        "output",
+        "path_aliases",
+        "Shorter names for directories in the tree and in file headers",
+        "\"packages/very-long-internal-name\" = \"pkg\"",
+    ),
+    table(
+        "output",
+        "max_size_by_ext",
+        "max_file_size for files with these extensions (0 for no limit)",
+        "json = \"20KB\"",
+    ),
+    field(
+        "filters",
+        "include_patterns",
+        "Only include contents of files matching these globs",
+    ),
+    field(
+        "filters",
+        "exclude_patterns",
+        "Skip files and directories matching these globs",
+    ),
+    field(
+        "filters",
+        "deep_patterns",
+        "Show contents only for these globs and the tree to overview_depth elsewhere",
+    ),
+    field(
+        "filters",
+        "default_excludes",
+        "Exclude .env, *.pem, *.key, id_rsa* and credentials.json",
+    ),
+    field(
+        "filters",
+        "summarize_patterns",
+        "List these files but replace their contents by a one-line summary (lockfiles, minified and generated code)",
+    ),
+    field(
+        "filters",
+        "exclude_owners",
+        "Skip contents of files owned by these users (Unix)",
+    ),
+    optional(
+        "filters",
+        "required_mode",
+        "Skip contents of files missing any of these permission bits (Unix)",
+        r#""040""#,
+    ),
+    field(
+        "filters",
+        "include_groups",
+        "Like include_patterns, with the patterns of these [groups]",
+    ),
+    field(
+        "filters",
+        "exclude_groups",
+        "Like exclude_patterns, with the patterns of these [groups]",
+    ),
+    field(
+        "io",
+        "max_open_files",
+        "Maximum number of files open at once (0 for no limit)",
+    ),
+    optional(
+        "io",
+        "bytes_per_sec",
+        "Maximum bytes read per second",
+        "10000000",
+    ),
+    field(
+        "io",
+        "max_buffer_size",
+        "Output kept in memory before spilling to a temporary file (0 for no limit)",
+    ),
+    field(
+        "io",
+        "write_buffer_size",
+        "Buffer in front of the output file or stdout (0 writes through immediately)",
+    ),
+    field(
+        "io",
+        "read_workers",
+        "Threads reading file contents ahead of the writer (0 or 1 reads one file at a time)",
+    ),
+    field(
+        "sampling",
+        "patterns",
+        "Include only a few files' contents per directory for these globs",
+    ),
+    field("sampling", "count", "Files sampled per directory"),
+    field("sampling", "seed", "The same seed picks the same files"),
+    field(
+        "redact",
+        "enabled",
+        "Replace likely secrets with [REDACTED]",
+    ),
+    field("redact", "patterns", "Extra regexes to redact"),
+    field(
+        "transform",
+        "strip_comments",
+        "Drop comments and blank lines from source files, by language",
+    ),
+    optional(
+        "hooks",
+        "pre",
+        "Command run before generating (only with --run-hooks)",
+        r#""cargo fmt --check""#,
+    ),
+    optional(
+        "hooks",
+        "post",
+        "Command run after generating (only with --run-hooks)",
+        r#""gzip -kf \"$PROJCT_OUTPUT\"""#,
+    ),
+    field(
+        "notifications",
+        "desktop",
+        "Show a desktop notification when the run finishes (runs notify-send on Linux, osascript on macOS; not available on Windows)",
+    ),
+    optional(
+        "notifications",
+        "webhook",
+        "POST the run summary as JSON to this URL when the run finishes (runs curl)",
+        r#""https://hooks.example.com/projct""#,
+    ),
+];
+
+pub fn default_config_toml() -> String {
+    let defaults = toml::Table::try_from(Config::default()).unwrap_or_default();
+    let mut out = String::from("# projct configuration with every setting at its default\n");
+    for section in SECTIONS {
+        let values = defaults.get(*section).and_then(toml::Value::as_table);
+        let fields = FIELD_DOCS.iter().filter(|f| f.section == *section);
+        let _ = writeln!(out, "\n[{}]", section);
+        let mut tables = vec![];
+        for field in fields {
+            let value = values.and_then(|v| v.get(field.key));
+            if field.table {
+                tables.push(field);
+                continue;
+            }
+            let _ = writeln!(out, "# {}", field.doc);
+            match (value, field.example) {
+                (Some(value), _) => {
+                    let _ = writeln!(out, "{} = {}", field.key, value);
+                }
+                (None, example) => {
+                    let _ = writeln!(out, "# {} = {}", field.key, example.unwrap_or("\"\""));
+                }
+            }
+        }
+        for field in tables {
+            let _ = writeln!(out, "\n# {}", field.doc);
+            let _ = writeln!(out, "[{}.{}]", section, field.key);
+            if let Some(example) = field.example {
+                let _ = writeln!(out, "# {}", example);
+            }
+        }
+    }
+    out.push_str(
+        "\n# Named pattern lists for filters.include_groups, filters.exclude_groups and output.group_order\n# [groups]\n# tests = [\"tests/**\", \"**/*_test.*\"]\n",
+    );
+    out.push_str(
+        "\n# Named overrides selected with --profile <name>\n# [profile.review.output]\n# format = \"markdown\"\n",
+    );
+    out
+}
@@ -0,0 +1,155 @@
+use std::path::Path;
+
+pub fn is_readme(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().to_lowercase().starts_with("readme"))
+}
+
+pub fn extract(language: &str, content: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let sections = match language {
+        "rust" => rust_docs(&lines),
+        "python" => python_docs(&lines),
+        "javascript" | "jsx" | "typescript" | "tsx" => js_docs(&lines),
+        _ => return None,
+    };
+    Some(
+        sections
+            .iter()
+            .map(|section| section.join("\n") + "\n")
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+fn signature(line: &str) -> String {
+    line.trim_end().trim_end_matches('{').trim_end().to_string()
+}
+
+fn rust_docs(lines: &[&str]) -> Vec<Vec<String>> {
+    let mut sections = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let marker = ["//!", "///"]
+            .into_iter()
+            .find(|marker| lines[i].starts_with(marker));
+        let Some(marker) = marker else {
+            i += 1;
+            continue;
+        };
+        let mut section = vec![];
+        while i < lines.len() && lines[i].starts_with(marker) {
+            section.push(lines[i].to_string());
+            i += 1;
+        }
+        if marker == "///" {
+            while i < lines.len() && lines[i].starts_with("#[") {
+                i += 1;
+            }
+            if let Some(item) = lines.get(i) {
+                section.push(signature(item));
+                i += 1;
+            }
+        }
+        sections.push(section);
+    }
+    sections
+}
+
+fn js_docs(lines: &[&str]) -> Vec<Vec<String>> {
+    let mut sections = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        if !lines[i].starts_with("/**") {
+            i += 1;
+            continue;
+        }
+        let mut section = vec![];
+        while i < lines.len() {
+            section.push(lines[i].to_string());
+            i += 1;
+            if section.last().is_some_and(|line| line.contains("*/")) {
+                break;
+            }
+        }
+        if let Some(item) = lines.get(i).filter(|line| !line.trim().is_empty()) {
+            section.push(signature(item));
+            i += 1;
+        }
+        sections.push(section);
+    }
+    sections
+}
+
+fn python_docs(lines: &[&str]) -> Vec<Vec<String>> {
+    let mut sections = vec![];
+    let first_statement = lines
+        .iter()
+        .position(|line| !line.trim().is_empty() && !line.starts_with('#'));
+    if let Some((docstring, _)) = first_statement.and_then(|start| docstring(lines, start)) {
+        sections.push(docstring);
+    }
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let is_definition = ["def ", "async def ", "class "]
+            .iter()
+            .any(|keyword| line.starts_with(keyword));
+        i += 1;
+        if !is_definition {
+            continue;
+        }
+        let mut section = vec![line.trim_end().to_string()];
+        let mut depth = open_brackets(line);
+        while depth > 0 && i < lines.len() {
+            section.push(lines[i].trim_end().to_string());
+            depth += open_brackets(lines[i]);
+            i += 1;
+        }
+        if !ends_signature(section.last().unwrap()) {
+            continue;
+        }
+        let body = (i..lines.len()).find(|&j| !lines[j].trim().is_empty());
+        if let Some((docstring, end)) = body.and_then(|start| docstring(lines, start)) {
+            section.extend(docstring);
+            sections.push(section);
+            i = end;
+        }
+    }
+    sections
+}
+
+fn open_brackets(line: &str) -> i32 {
+    line.chars()
+        .map(|c| match c {
+            '(' | '[' => 1,
+            ')' | ']' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+fn ends_signature(line: &str) -> bool {
+    line.split(" #")
+        .next()
+        .unwrap_or(line)
+        .trim_end()
+        .ends_with(':')
+}
+
+fn docstring(lines: &[&str], start: usize) -> Option<(Vec<String>, usize)> {
+    let first = lines[start].trim_start();
+    let body = first.trim_start_matches(['r', 'R', 'u', 'U']);
+    let quote = ["\"\"\"", "'''"]
+        .into_iter()
+        .find(|quote| body.starts_with(quote))?;
+    let mut end = start;
+    if !body[quote.len()..].contains(quote) {
+        end = (start + 1..lines.len()).find(|&j| lines[j].contains(quote))?;
+    }
+    let docstring = lines[start..=end]
+        .iter()
+        .map(|line| line.trim_end().to_string())
+        .collect();
+    Some((docstring, end + 1))
+}
@@ -0,0 +1,189 @@
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+use std::process::Command;
+
+use crate::clipboard;
+use crate::config::{Args, Config};
+use crate::git::Repository;
+use crate::lint;
+use crate::selftest;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warning,
+    Failure,
+}
+
+pub struct Diagnostic {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Diagnostic {
+    fn ok(name: &str, detail: String) -> Self {
+        Diagnostic {
+            name: name.to_string(),
+            status: Status::Ok,
+            detail,
+            fix: None,
+        }
+    }
+
+    fn problem(name: &str, status: Status, detail: String, fix: &str) -> Self {
+        Diagnostic {
+            name: name.to_string(),
+            status,
+            detail,
+            fix: Some(fix.to_string()),
+        }
+    }
+}
+
+pub fn diagnose(config_path: &str, args: &Args, paths: Vec<String>) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let config = match Config::new(config_path, args, paths) {
+        Ok(config) => {
+            diagnostics.push(check_config_file(config_path));
+            config
+        }
+        Err(e) => {
+            diagnostics.push(Diagnostic::problem(
+                "config",
+                Status::Failure,
+                e.to_string(),
+                "Fix the reported setting, or move the file away to use the defaults",
+            ));
+            Config::default()
+        }
+    };
+    diagnostics.push(check_git());
+    for root in config.roots() {
+        diagnostics.push(check_repository(&root));
+        diagnostics.push(check_ignore_patterns(&root));
+    }
+    diagnostics.push(check_output(&config));
+    diagnostics.push(check_clipboard());
+    for check in selftest::selftest() {
+        let name = format!("selftest: {}", check.name);
+        diagnostics.push(match check.result {
+            Ok(()) => Diagnostic::ok(&name, "passed".to_string()),
+            Err(message) => Diagnostic::problem(
+                &name,
+                Status::Failure,
+                message,
+                "Please report this with your platform and file system",
+            ),
+        });
+    }
+    diagnostics
+}
+
+fn check_config_file(config_path: &str) -> Diagnostic {
+    if Path::new(config_path).exists() {
+        Diagnostic::ok("config", format!("{} parsed", config_path))
+    } else {
+        Diagnostic::problem(
+            "config",
+            Status::Warning,
+            format!("{} not found, using defaults", config_path),
+            "Run `projct init` to create a config file",
+        )
+    }
+}
+
+fn check_git() -> Diagnostic {
+    match Command::new("git").arg("--version").output() {
+        Ok(output) if output.status.success() => Diagnostic::ok(
+            "git",
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ),
+        _ => Diagnostic::problem(
+            "git",
+            Status::Warning,
+            "git not found on PATH".to_string(),
+            "projct reads ignore files itself, but install git to manage the repositories it scans",
+        ),
+    }
+}
+
+fn check_repository(root: &str) -> Diagnostic {
+    let name = format!("repository ({})", root);
+    if !Path::new(root).exists() {
+        return Diagnostic::problem(
+            &name,
+            Status::Failure,
+            "start path does not exist".to_string(),
+            "Pass an existing directory or fix general.path",
+        );
+    }
+    match Repository::discover(Path::new(root)) {
+        Some(repo) => Diagnostic::ok(&name, format!("work tree {}", repo.work_tree.display())),
+        None => Diagnostic::problem(
+            &name,
+            Status::Warning,
+            "not inside a git repository, so .git/info/exclude and core.excludesFile are not applied".to_string(),
+            "Scan a directory inside the repository, or ignore this if the project is not under git",
+        ),
+    }
+}
+
+fn check_ignore_patterns(root: &str) -> Diagnostic {
+    let name = format!("ignore patterns ({})", root);
+    let issues = lint::lint_ignores(Path::new(root));
+    match issues.first() {
+        None => Diagnostic::ok(&name, "no suspicious patterns".to_string()),
+        Some(first) => Diagnostic::problem(
+            &name,
+            Status::Warning,
+            format!(
+                "{} suspicious pattern{}, e.g. {}",
+                issues.len(),
+                if issues.len() == 1 { "" } else { "s" },
+                first
+            ),
+            "Run `projct lint-ignores` for the full list",
+        ),
+    }
+}
+
+fn check_output(config: &Config) -> Diagnostic {
+    let filename = &config.output.filename;
+    if filename.is_empty() || filename == "-" {
+        return Diagnostic::ok("output", "writing to stdout".to_string());
+    }
+    let dir = Path::new(filename)
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let probe = dir.join(format!(".projct-doctor-{}", std::process::id()));
+    match OpenOptions::new().write(true).create_new(true).open(&probe) {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+            Diagnostic::ok("output", format!("{} is writable", dir.display()))
+        }
+        Err(e) => Diagnostic::problem(
+            "output",
+            Status::Failure,
+            format!("cannot write to {}: {}", dir.display(), e),
+            "Choose a writable location with -o, or use -o - for stdout",
+        ),
+    }
+}
+
+fn check_clipboard() -> Diagnostic {
+    match clipboard::available_backends().first() {
+        Some(backend) => Diagnostic::ok("clipboard", format!("using {}", backend)),
+        None => Diagnostic::problem(
+            "clipboard",
+            Status::Warning,
+            format!(
+                "none of {} found on PATH",
+                clipboard::backend_names().join(", ")
+            ),
+            "Install one of them to use --clipboard",
+        ),
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    Config {
+        path: PathBuf,
+        message: String,
+    },
+    CreateConfig {
+        path: PathBuf,
+        source: io::Error,
+    },
+    StartPath {
+        path: PathBuf,
+    },
+    Output {
+        path: PathBuf,
+        source: io::Error,
+    },
+    NameCollision {
+        path: PathBuf,
+    },
+    Clipboard(String),
+    Cache {
+        path: PathBuf,
+        source: io::Error,
+    },
+    CacheUnavailable,
+    Editor(String),
+    NoLastRun {
+        path: PathBuf,
+    },
+    Chdir {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Import {
+        path: PathBuf,
+        message: String,
+    },
+    Hook {
+        stage: &'static str,
+        message: String,
+    },
+    LintFindings(usize),
+    AuditFindings {
+        count: usize,
+        allowed: usize,
+    },
+    DoctorFindings(usize),
+    Usage(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Config { path, message } => {
+                write!(f, "Cannot load config {}: {}", path.display(), message)
+            }
+            Error::CreateConfig { path, source } => {
+                write!(f, "Cannot create config {}: {}", path.display(), source)
+            }
+            Error::StartPath { path } => {
+                write!(f, "Start path does not exist: {}", path.display())
+            }
+            Error::Output { path, source } => {
+                write!(f, "Cannot write output {}: {}", path.display(), source)
+            }
+            Error::NameCollision { path } => write!(
+                f,
+                "Output name collision: {} would be written more than once (check output.name_template)",
+                path.display()
+            ),
+            Error::Clipboard(message) => write!(f, "Cannot copy to clipboard: {}", message),
+            Error::Cache { path, source } => {
+                write!(f, "Cannot update cache {}: {}", path.display(), source)
+            }
+            Error::CacheUnavailable => write!(f, "Cannot determine the cache directory"),
+            Error::Editor(message) => write!(f, "{}", message),
+            Error::NoLastRun { path } => {
+                write!(f, "No previous run recorded ({} not found)", path.display())
+            }
+            Error::Chdir { path, source } => {
+                write!(f, "Cannot change directory to {}: {}", path.display(), source)
+            }
+            Error::Import { path, message } => {
+                write!(f, "Cannot import {}: {}", path.display(), message)
+            }
+            Error::Hook { stage, message } => write!(f, "{} hook failed: {}", stage, message),
+            Error::LintFindings(count) => write!(
+                f,
+                "{} problem{} found in ignore rules",
+                count,
+                if *count == 1 { "" } else { "s" }
+            ),
+            Error::AuditFindings { count, allowed } => write!(
+                f,
+                "{} potential finding{} ({} allowed)",
+                count,
+                if *count == 1 { "" } else { "s" },
+                allowed
+            ),
+            Error::DoctorFindings(count) => write!(
+                f,
+                "{} doctor check{} failed",
+                count,
+                if *count == 1 { "" } else { "s" }
+            ),
+            Error::Usage(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::CreateConfig { source, .. }
+            | Error::Output { source, .. }
+            | Error::Cache { source, .. }
+            | Error::Chdir { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
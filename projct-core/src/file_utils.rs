@@ -1,29 +1,539 @@
-use std::fs::File;
-use std::io::Read;
+use std::collections::BTreeMap;
+use std::fs::Metadata;
+use std::io::{self, BufRead, BufReader, Read};
 use std::path::Path;
 
+use crate::config::{BinaryDetection, FrontMatterMode};
+use crate::throttle::IoThrottle;
+
+const MARKER_SCAN_LINES: usize = 10;
+const BINARY_SCAN_BYTES: usize = 1024;
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "tiff", "psd", "zip", "gz", "tgz", "bz2",
+    "xz", "zst", "7z", "rar", "tar", "jar", "war", "whl", "pdf", "doc", "docx", "xls", "xlsx",
+    "ppt", "pptx", "exe", "dll", "so", "dylib", "a", "o", "obj", "lib", "rlib", "class", "pyc",
+    "wasm", "bin", "dat", "db", "sqlite", "woff", "woff2", "ttf", "otf", "eot", "mp3", "mp4",
+    "wav", "ogg", "flac", "avi", "mov", "mkv", "webm",
+];
+const TEXT_EXTENSIONS: &[&str] = &[
+    "rs", "toml", "md", "txt", "py", "js", "ts", "jsx", "tsx", "json", "yaml", "yml", "html",
+    "css", "scss", "c", "h", "cc", "cpp", "hpp", "go", "java", "kt", "rb", "php", "sh", "bash",
+    "zsh", "sql", "xml", "csv", "ini", "cfg", "lock", "swift", "cs", "lua", "vue", "svelte",
+];
+const MAGIC_BYTES: &[&[u8]] = &[
+    b"\x89PNG",
+    b"GIF8",
+    b"\xff\xd8\xff",
+    b"%PDF-",
+    b"PK\x03\x04",
+    b"\x1f\x8b",
+    b"\x7fELF",
+    b"\xca\xfe\xba\xbe",
+    b"\xcf\xfa\xed\xfe",
+    b"\xfe\xed\xfa\xcf",
+    b"7z\xbc\xaf\x27\x1c",
+    b"\xfd7zXZ",
+    b"Rar!",
+    b"OggS",
+];
+const UTF16_BOMS: &[&[u8]] = &[b"\xff\xfe", b"\xfe\xff"];
+const CP1252_HIGH: [u16; 32] = [
+    0x20ac, 0x81, 0x201a, 0x0192, 0x201e, 0x2026, 0x2020, 0x2021, 0x02c6, 0x2030, 0x0160, 0x2039,
+    0x0152, 0x8d, 0x017d, 0x8f, 0x90, 0x2018, 0x2019, 0x201c, 0x201d, 0x2022, 0x2013, 0x2014,
+    0x02dc, 0x2122, 0x0161, 0x203a, 0x0153, 0x9d, 0x017e, 0x0178,
+];
+const IGNORE_FILE_MARKER: &str = "projct:ignore-file";
+const INCLUDE_MARKER: &str = "projct:include";
+const BEGIN_IGNORE_MARKER: &str = "projct:begin-ignore";
+const END_IGNORE_MARKER: &str = "projct:end-ignore";
+const COMMENT_PREFIXES: &[&str] = &["//", "#", "/*", "*", "<!--", "--", ";", "%", "'"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileMarker {
+    IgnoreFile,
+    Include,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextProbe {
+    pub is_text: bool,
+    pub lines: Option<usize>,
+}
+
 pub struct FileUtils;
 
 impl FileUtils {
-    pub fn is_text_file(filepath: &Path) -> bool {
-        let mut file = match File::open(filepath) {
-            Ok(f) => f,
-            Err(_) => return false,
+    pub fn is_text_file(filepath: &Path, throttle: &IoThrottle) -> bool {
+        Self::probe_text(filepath, throttle).is_text
+    }
+
+    pub fn probe_text(filepath: &Path, throttle: &IoThrottle) -> TextProbe {
+        Self::scan_text(filepath, throttle, false, false).0
+    }
+
+    pub fn probe_bytes(bytes: &[u8]) -> TextProbe {
+        Self::scan_reader(bytes, false, false).0
+    }
+
+    pub fn detect_text(
+        filepath: &Path,
+        throttle: &IoThrottle,
+        detection: BinaryDetection,
+        need_lines: bool,
+        keep: bool,
+    ) -> (TextProbe, Option<Vec<u8>>) {
+        if detection == BinaryDetection::Full {
+            return Self::scan_text(filepath, throttle, false, keep);
+        }
+        let extension = filepath
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if BINARY_EXTENSIONS.contains(&extension.as_str()) {
+            let probe = TextProbe {
+                is_text: false,
+                lines: None,
+            };
+            return (probe, None);
+        }
+        if TEXT_EXTENSIONS.contains(&extension.as_str()) && !need_lines {
+            let probe = TextProbe {
+                is_text: true,
+                lines: None,
+            };
+            return (probe, keep.then(|| throttle.read(filepath).ok()).flatten());
+        }
+        Self::scan_text(filepath, throttle, true, keep)
+    }
+
+    fn scan_text(
+        filepath: &Path,
+        throttle: &IoThrottle,
+        sniff: bool,
+        keep: bool,
+    ) -> (TextProbe, Option<Vec<u8>>) {
+        match throttle.open(filepath) {
+            Ok(file) => Self::scan_reader(file, sniff, keep),
+            Err(e) => {
+                let probe = TextProbe {
+                    is_text: Self::is_locked_error(&e),
+                    lines: None,
+                };
+                (probe, None)
+            }
+        }
+    }
+
+    fn scan_reader(mut file: impl Read, sniff: bool, keep: bool) -> (TextProbe, Option<Vec<u8>>) {
+        let not_text = || {
+            let probe = TextProbe {
+                is_text: false,
+                lines: None,
+            };
+            (probe, None)
+        };
+        let mut kept = Vec::new();
+        let mut buffer = [0u8; 8192];
+        let mut pending = Vec::new();
+        let mut scanned = 0;
+        let mut lines = 0;
+        let mut last = b'\n';
+        let mut legacy = false;
+        let mut control = false;
+        loop {
+            let Ok(len) = file.read(&mut buffer) else {
+                return not_text();
+            };
+            if len == 0 {
+                break;
+            }
+            let chunk = &buffer[..len];
+            if scanned == 0 {
+                if UTF16_BOMS.iter().any(|bom| chunk.starts_with(bom)) {
+                    let mut bytes = chunk.to_vec();
+                    if file.read_to_end(&mut bytes).is_err() {
+                        return not_text();
+                    }
+                    let kept = keep.then(|| bytes.clone());
+                    let content = Self::decode_text(bytes).map(|(content, _)| content);
+                    let probe = TextProbe {
+                        is_text: content.is_some(),
+                        lines: content.map(|c| c.lines().count()),
+                    };
+                    return (probe, kept);
+                }
+                if sniff && MAGIC_BYTES.iter().any(|m| chunk.starts_with(m)) {
+                    return not_text();
+                }
+            }
+            if scanned < BINARY_SCAN_BYTES {
+                let end = len.min(BINARY_SCAN_BYTES - scanned);
+                if chunk[..end].contains(&0) {
+                    return not_text();
+                }
+                scanned += end;
+            }
+            if keep {
+                kept.extend_from_slice(chunk);
+            }
+            lines += chunk.iter().filter(|&&b| b == b'\n').count();
+            last = chunk[len - 1];
+            control |= chunk.iter().any(|&b| Self::is_control_byte(b));
+            if !legacy {
+                pending.extend_from_slice(chunk);
+                match std::str::from_utf8(&pending) {
+                    Ok(_) => pending.clear(),
+                    Err(e) if e.error_len().is_none() => {
+                        pending.drain(..e.valid_up_to());
+                    }
+                    Err(_) => legacy = true,
+                }
+            }
+            if legacy && control {
+                return not_text();
+            }
+        }
+        let probe = TextProbe {
+            is_text: (!legacy && pending.is_empty()) || !control,
+            lines: Some(lines + usize::from(last != b'\n')),
+        };
+        (probe, keep.then_some(kept))
+    }
+
+    pub fn decode_text(bytes: Vec<u8>) -> Option<(String, Option<&'static str>)> {
+        if let Some(rest) = bytes.strip_prefix(b"\xff\xfe") {
+            return Some((
+                Self::decode_utf16(rest, u16::from_le_bytes),
+                Some("UTF-16LE"),
+            ));
+        }
+        if let Some(rest) = bytes.strip_prefix(b"\xfe\xff") {
+            return Some((
+                Self::decode_utf16(rest, u16::from_be_bytes),
+                Some("UTF-16BE"),
+            ));
+        }
+        match String::from_utf8(bytes) {
+            Ok(content) => Some((content, None)),
+            Err(e) => {
+                let bytes = e.into_bytes();
+                if bytes.iter().any(|&b| Self::is_control_byte(b)) {
+                    return None;
+                }
+                let content = bytes
+                    .iter()
+                    .map(|&b| match b {
+                        0x80..=0x9f => {
+                            char::from_u32(u32::from(CP1252_HIGH[usize::from(b - 0x80)]))
+                                .unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => char::from(b),
+                    })
+                    .collect();
+                Some((content, Some("Windows-1252")))
+            }
+        }
+    }
+
+    fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+        let units = bytes
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]));
+        char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    fn is_control_byte(byte: u8) -> bool {
+        (byte < 0x20 && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0c)) || byte == 0x7f
+    }
+
+    pub fn file_marker(filepath: &Path, throttle: &IoThrottle) -> Option<FileMarker> {
+        let file = throttle.open(filepath).ok()?;
+        Self::scan_marker(BufReader::new(file))
+    }
+
+    pub fn marker_in(bytes: &[u8]) -> Option<FileMarker> {
+        Self::scan_marker(bytes)
+    }
+
+    fn scan_marker(reader: impl BufRead) -> Option<FileMarker> {
+        for line in reader.lines().take(MARKER_SCAN_LINES) {
+            let line = line.ok()?;
+            if !Self::is_comment_line(&line) {
+                continue;
+            }
+            if line.contains(IGNORE_FILE_MARKER) {
+                return Some(FileMarker::IgnoreFile);
+            }
+            if line.contains(INCLUDE_MARKER) {
+                return Some(FileMarker::Include);
+            }
+        }
+        None
+    }
+
+    pub fn strip_ignored_regions(content: &str) -> String {
+        if !content.contains(BEGIN_IGNORE_MARKER) {
+            return content.to_string();
+        }
+        let mut result = String::with_capacity(content.len());
+        let mut in_region = false;
+        for line in content.lines() {
+            let is_comment = Self::is_comment_line(line);
+            if !in_region && is_comment && line.contains(BEGIN_IGNORE_MARKER) {
+                in_region = true;
+                result.push_str("[section omitted]\n");
+            } else if in_region {
+                if is_comment && line.contains(END_IGNORE_MARKER) {
+                    in_region = false;
+                }
+            } else {
+                result.push_str(line);
+                result.push('\n');
+            }
+        }
+        result
+    }
+
+    pub fn process_front_matter(filepath: &Path, content: &str, mode: FrontMatterMode) -> String {
+        let is_markdown = filepath
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("md") || e.eq_ignore_ascii_case("markdown"));
+        if mode == FrontMatterMode::Keep || !is_markdown {
+            return content.to_string();
+        }
+        let mut lines = content.split_inclusive('\n');
+        let Some(first) = lines.next() else {
+            return content.to_string();
         };
-        let mut chunk = vec![0u8; 1024];
-        let len = match file.read(&mut chunk) {
-            Ok(l) => l,
-            Err(_) => return false,
+        if first.trim_end() != "---" {
+            return content.to_string();
+        }
+        let mut keys = vec![];
+        let mut body_start = None;
+        let mut offset = first.len();
+        for line in lines {
+            offset += line.len();
+            let trimmed = line.trim_end();
+            if trimmed == "---" || trimmed == "..." {
+                body_start = Some(offset);
+                break;
+            }
+            if !line.starts_with([' ', '\t', '-', '#']) {
+                if let Some((key, _)) = line.split_once(':') {
+                    keys.push(key.trim().to_string());
+                }
+            }
+        }
+        let Some(body_start) = body_start else {
+            return content.to_string();
         };
-        if chunk[..len].contains(&0) {
-            return false;
+        let body = content[body_start..].trim_start_matches(['\r', '\n']);
+        match mode {
+            FrontMatterMode::Summarize => {
+                format!("[front matter: {}]\n{}", keys.join(", "), body)
+            }
+            _ => body.to_string(),
+        }
+    }
+
+    fn is_comment_line(line: &str) -> bool {
+        let trimmed = line.trim_start();
+        COMMENT_PREFIXES.iter().any(|p| trimmed.starts_with(p))
+    }
+
+    pub fn is_hidden(path: &Path) -> bool {
+        path.file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+    }
+
+    #[cfg(unix)]
+    pub fn hard_link_identity(metadata: &Metadata) -> Option<(u64, u64)> {
+        use std::os::unix::fs::MetadataExt;
+        (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+    }
+
+    #[cfg(not(unix))]
+    pub fn hard_link_identity(_metadata: &Metadata) -> Option<(u64, u64)> {
+        None
+    }
+
+    #[cfg(windows)]
+    pub fn is_locked_error(error: &io::Error) -> bool {
+        const ERROR_SHARING_VIOLATION: i32 = 32;
+        const ERROR_LOCK_VIOLATION: i32 = 33;
+        matches!(
+            error.raw_os_error(),
+            Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+        )
+    }
+
+    #[cfg(not(windows))]
+    pub fn is_locked_error(_error: &io::Error) -> bool {
+        false
+    }
+
+    pub fn entropy(bytes: &[u8]) -> f64 {
+        let mut counts = [0usize; 256];
+        for &byte in bytes {
+            counts[byte as usize] += 1;
         }
-        drop(file);
-        let mut file = match File::open(filepath) {
-            Ok(f) => f,
-            Err(_) => return false,
+        let len = bytes.len() as f64;
+        counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / len;
+                -p * p.log2()
+            })
+            .sum()
+    }
+
+    pub fn format_size(bytes: u64) -> String {
+        const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+        if bytes < 1024 {
+            return format!("{} B", bytes);
+        }
+        let mut size = bytes as f64 / 1024.0;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+
+    pub fn parse_size(text: &str) -> Result<u64, String> {
+        let text = text.trim();
+        let split = text
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+        let invalid = || format!("invalid size `{}` (expected e.g. 50MB)", text);
+        let number: f64 = number.parse().map_err(|_| invalid())?;
+        let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "K" | "KB" | "KIB" => 1 << 10,
+            "M" | "MB" | "MIB" => 1 << 20,
+            "G" | "GB" | "GIB" => 1 << 30,
+            "T" | "TB" | "TIB" => 1 << 40,
+            _ => return Err(invalid()),
         };
-        let mut content = String::new();
-        file.read_to_string(&mut content).is_ok()
+        Ok((number * multiplier as f64) as u64)
+    }
+
+    pub fn format_count(count: usize) -> String {
+        let digits = count.to_string();
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push(',');
+            }
+            out.push(digit);
+        }
+        out
+    }
+
+    pub fn truncate_lines(content: &str, head: usize, tail: usize) -> Option<String> {
+        let lines: Vec<&str> = content.lines().collect();
+        if lines.len() <= head + tail {
+            return None;
+        }
+        let mut out = String::new();
+        for line in &lines[..head] {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "[... truncated {} lines ...]\n",
+            Self::format_count(lines.len() - head - tail)
+        ));
+        for line in &lines[lines.len() - tail..] {
+            out.push_str(line);
+            out.push('\n');
+        }
+        Some(out)
+    }
+
+    pub fn xml_escape(text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    pub fn fence_language<'a>(
+        filepath: &Path,
+        language_map: &'a BTreeMap<String, String>,
+    ) -> &'a str {
+        let file_name = filepath
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if let Some(language) = language_map.get(&file_name) {
+            return language;
+        }
+        let mapped_extension = filepath
+            .extension()
+            .and_then(|e| language_map.get(&e.to_string_lossy().to_lowercase()));
+        if let Some(language) = mapped_extension {
+            return language;
+        }
+        match file_name.as_str() {
+            "dockerfile" => return "dockerfile",
+            "makefile" | "gnumakefile" => return "makefile",
+            "cmakelists.txt" => return "cmake",
+            _ => {}
+        }
+        let extension = filepath
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "rs" => "rust",
+            "py" | "pyi" => "python",
+            "js" | "mjs" | "cjs" => "javascript",
+            "jsx" => "jsx",
+            "ts" | "mts" | "cts" => "typescript",
+            "tsx" => "tsx",
+            "go" => "go",
+            "java" => "java",
+            "kt" | "kts" => "kotlin",
+            "swift" => "swift",
+            "c" | "h" => "c",
+            "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => "cpp",
+            "cs" => "csharp",
+            "rb" => "ruby",
+            "php" => "php",
+            "lua" => "lua",
+            "sh" | "bash" | "zsh" => "bash",
+            "ps1" => "powershell",
+            "sql" => "sql",
+            "html" | "htm" => "html",
+            "css" => "css",
+            "scss" => "scss",
+            "vue" => "vue",
+            "svelte" => "svelte",
+            "json" => "json",
+            "toml" => "toml",
+            "yaml" | "yml" => "yaml",
+            "xml" => "xml",
+            "md" | "markdown" => "markdown",
+            "ini" | "cfg" => "ini",
+            "proto" => "protobuf",
+            "dart" => "dart",
+            "scala" => "scala",
+            "hs" => "haskell",
+            "ex" | "exs" => "elixir",
+            "zig" => "zig",
+            _ => "",
+        }
     }
 }
@@ -0,0 +1,92 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const DEFAULT_EXCLUDE_PATTERNS: &[&str] =
+    &[".env", "*.pem", "*.key", "id_rsa*", "credentials.json"];
+
+pub const DEFAULT_SUMMARIZE_PATTERNS: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "poetry.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+    "*.min.js",
+    "*.min.css",
+    "*.pb.go",
+    "*_pb2.py",
+    "*.pb.h",
+    "*.pb.cc",
+];
+
+pub fn relative_path(path: &Path, start_path: &Path) -> String {
+    slash_path(path.strip_prefix(start_path).unwrap_or(path))
+}
+
+#[cfg(windows)]
+pub fn slash_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(not(windows))]
+pub fn slash_path(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+#[cfg(windows)]
+fn slash_pattern(pattern: &str) -> Cow<'_, str> {
+    Cow::Owned(pattern.replace('\\', "/"))
+}
+
+#[cfg(not(windows))]
+fn slash_pattern(pattern: &str) -> Cow<'_, str> {
+    Cow::Borrowed(pattern)
+}
+
+pub fn apply_alias(aliases: &BTreeMap<String, String>, rel_path: &str) -> String {
+    let alias = aliases
+        .iter()
+        .filter(|(prefix, _)| {
+            rel_path == prefix.as_str()
+                || rel_path
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .max_by_key(|(prefix, _)| prefix.len());
+    match alias {
+        Some((prefix, alias)) => format!("{}{}", alias, &rel_path[prefix.len()..]),
+        None => rel_path.to_string(),
+    }
+}
+
+pub fn pattern_matches(pattern: &str, rel_path: &str) -> bool {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let pattern = slash_pattern(pattern);
+    let target = if pattern.contains('/') {
+        rel_path
+    } else {
+        rel_path.rsplit('/').next().unwrap_or(rel_path)
+    };
+    glob::Pattern::new(pattern.trim_start_matches('/'))
+        .ok()
+        .is_some_and(|gp| gp.matches_with(target, options))
+}
+
+pub fn matches_any(patterns: &[String], rel_path: &str) -> bool {
+    patterns.iter().any(|p| pattern_matches(p, rel_path))
+}
+
+pub fn excludes_dir(patterns: &[String], rel_path: &str) -> bool {
+    patterns.iter().any(|p| {
+        let p = slash_pattern(p);
+        p.strip_suffix("/**")
+            .or_else(|| p.strip_suffix('/'))
+            .is_some_and(|dir_pattern| pattern_matches(dir_pattern, rel_path))
+    })
+}
@@ -1,73 +1,131 @@
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::config::Config;
-use crate::file_utils::FileUtils;
-use crate::gitignore::HierarchicalGitignoreManager;
+use crate::ascii::{self, AsciiWriter};
+use crate::cache;
+use crate::clipboard;
+use crate::comments;
+use crate::config::{
+    self as config, AnonymizeMode, BinaryDetection, Config, ContentOrder, OutputFormat, SortOrder,
+    SummaryMode, SymlinkMode, TreeStyle, TruncateLines,
+};
+use crate::docs;
+use crate::error::{self, Error};
+use crate::file_utils::{FileMarker, FileUtils, TextProbe};
+use crate::filters;
+use crate::git::{CommitInfo, GitPathSet};
+use crate::gitignore::{self, HierarchicalGitignoreManager};
+use crate::hooks;
+use crate::json::{self, FileTimings, ImportedFile, ImportedTree, JsonNode, JsonOutput, SlowFile};
+use crate::lfs::LfsPointer;
+use crate::licenses::LicenseDetector;
+use crate::limit::{self, LimitWriter};
+use crate::lock;
+use crate::manifest::{self, ManifestEntry};
+use crate::naming::{self, OutputName, OutputNamer};
+use crate::notifications::{self, Completion};
+use crate::permissions::PermissionFilter;
+use crate::prefetch::{self, Prefetched, ReadCache};
+use crate::progress::{Progress, ProgressEvent, ProgressWriter};
+use crate::redact::Redactor;
+use crate::sampling;
+use crate::sha256;
+use crate::spill::SpillBuffer;
+use crate::summary::RunSummary;
+use crate::throttle::IoThrottle;
+use crate::tokens::TokenCounter;
+use crate::transform::ContentTransform;
+use crate::tree::{NodeKind, ProjectTree, TreeNode};
+use unicode_width::UnicodeWidthStr;
+
+const MIN_ENTROPY_SAMPLE: usize = 512;
+const SLOWEST_FILES: usize = 10;
 
 pub struct OutputWriter<'a> {
     pub config: &'a Config,
     pub gitignore_manager: Option<&'a HierarchicalGitignoreManager>,
+    pub tracked_files: Option<&'a GitPathSet>,
+    pub changed_files: Option<&'a GitPathSet>,
+    pub throttle: &'a IoThrottle,
+    pub token_counter: Option<&'a TokenCounter>,
+    pub redactor: Option<&'a Redactor>,
+    pub transforms: &'a [Arc<dyn ContentTransform>],
+    pub license_detector: Option<&'a LicenseDetector>,
+    pub licenses: RefCell<BTreeMap<String, Vec<String>>>,
+    pub output_names: Vec<String>,
+    pub warnings: RefCell<Vec<String>>,
+    pub seen_inodes: RefCell<HashMap<(u64, u64), String>>,
+    pub token_counts: RefCell<Vec<(String, usize)>>,
+    pub imported: Option<HashMap<PathBuf, ImportedFile>>,
+    pub unsampled: HashSet<PathBuf>,
+    pub sensitive: HashSet<PathBuf>,
+    pub summarized: HashSet<PathBuf>,
+    pub oversized_dirs: Vec<(PathBuf, u64)>,
+    pub last_read: Cell<Duration>,
+    pub prefetched: RefCell<Option<io::Result<Vec<u8>>>>,
+    pub read_cache: Option<ReadCache>,
+    pub last_digest: RefCell<Option<String>>,
+    pub document_index: Cell<usize>,
+    pub color: bool,
+    pub summary: RefCell<RunSummary>,
+    pub manifest: RefCell<Vec<ManifestEntry>>,
+    pub progress: &'a Progress,
 }
 
 impl<'a> OutputWriter<'a> {
-    pub fn write_tree_and_get_files(
-        &self,
-        start_path: &Path,
-        output_file: &mut dyn Write,
-        depth: u32,
-        prefix: &str,
-    ) -> Vec<PathBuf> {
-        if let Some(md) = self.config.general.max_depth {
-            if depth > md {
-                return vec![];
-            }
-        }
-
-        let is_directory = start_path.is_dir();
-        let show_ignored = self.config.general.show_ignored;
-        let show_binary = self.config.general.show_binary;
-        let output_filename = &self.config.output.filename;
-
-        if start_path
-            .file_name()
-            .map_or(false, |name| name == output_filename.as_str())
+    pub fn build_tree(&self, start_path: &Path, root_name: &str) -> ProjectTree {
+        let mut root = if start_path.is_dir() {
+            let mut node = TreeNode::new(root_name, start_path, NodeKind::Directory);
+            self.populate_children(&mut node, 0);
+            node.size = node.children.iter().map(|c| c.size).sum();
+            node
+        } else if self.probe(start_path, BinaryDetection::Full, false).is_text
+            || self.config.general.show_binary
         {
-            return vec![];
-        }
-
-        let mut is_ignored = false;
-        if let Some(gm) = self.gitignore_manager {
-            if depth > 0 {
-                is_ignored = gm.should_ignore(start_path, is_directory);
-            }
-        }
-
-        if is_ignored && !show_ignored {
-            return vec![];
+            TreeNode::new(root_name, start_path, NodeKind::File)
+        } else {
+            TreeNode::new(root_name, start_path, NodeKind::Binary)
+        };
+        if !root.is_dir() {
+            root.size = start_path.metadata().map_or(0, |m| m.len());
         }
-
-        if !is_directory {
-            if !FileUtils::is_text_file(start_path) {
-                if !show_binary {
-                    return vec![];
-                }
-            }
+        ProjectTree {
+            root,
+            warnings: self.warnings.borrow().clone(),
         }
+    }
 
-        if !is_directory {
-            return vec![start_path.to_path_buf()];
+    fn populate_children(&self, node: &mut TreeNode, depth: u32) {
+        if self.config.general.max_depth.is_some_and(|md| depth > md) {
+            node.hidden = std::fs::read_dir(&node.path).ok().map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .filter(|e| {
+                        let is_dir = e.file_type().is_ok_and(|t| t.is_dir());
+                        !self.is_filtered_out(&e.path(), is_dir)
+                    })
+                    .count()
+            });
+            return;
         }
+        let show_ignored = self.config.general.show_ignored;
+        let show_binary = self.config.general.show_binary;
 
-        let mut collected_files = vec![];
-        let entries = match std::fs::read_dir(start_path) {
+        let entries = match std::fs::read_dir(&node.path) {
             Ok(e) => e,
             Err(_) => {
-                let _ = writeln!(output_file, "{}└── [Permission Denied]", prefix);
-                return collected_files;
+                node.kind = NodeKind::Unreadable;
+                return;
             }
         };
+        self.progress.emit(ProgressEvent::DirectoryEntered {
+            path: node.path.clone(),
+        });
 
         let mut items: Vec<PathBuf> = entries.filter_map(Result::ok).map(|e| e.path()).collect();
 
@@ -83,54 +141,464 @@ impl<'a> OutputWriter<'a> {
             }
         });
 
-        let num_items = items.len();
-        for (i, item_path) in items.iter().enumerate() {
-            let is_last = i == num_items - 1;
-            let connector = if is_last { "└── " } else { "├── " };
+        for item_path in &items {
+            if gitignore::is_git_dir(item_path) {
+                continue;
+            }
             let item_is_dir = item_path.is_dir();
 
             let item_is_ignored = self
                 .gitignore_manager
                 .as_ref()
-                .map_or(false, |gm| gm.should_ignore(item_path, item_is_dir));
+                .is_some_and(|gm| gm.should_ignore(item_path, item_is_dir));
             if item_is_ignored && !show_ignored {
+                self.summary.borrow_mut().skipped_ignored += 1;
+                self.count_excluded(item_path);
                 continue;
             }
 
-            if !item_is_dir && !FileUtils::is_text_file(item_path) && !show_binary {
+            if self
+                .tracked_files
+                .is_some_and(|tracked| !tracked.contains(item_path, item_is_dir))
+            {
+                self.summary.borrow_mut().skipped_ignored += 1;
+                self.count_excluded(item_path);
                 continue;
             }
-            if item_path
-                .file_name()
-                .map_or(false, |name| name == output_filename.as_str())
+
+            if self
+                .changed_files
+                .is_some_and(|changed| !changed.contains(item_path, item_is_dir))
             {
+                self.summary.borrow_mut().skipped_filtered += 1;
+                self.count_excluded(item_path);
                 continue;
             }
 
-            let display_name = item_path.file_name().unwrap().to_string_lossy();
-            let _ = writeln!(
-                output_file,
-                "{}{}{}{}",
-                prefix,
-                connector,
-                display_name,
-                if item_is_dir { "/" } else { "" }
-            );
+            if item_is_dir && self.is_excluded_dir(item_path) {
+                self.summary.borrow_mut().skipped_filtered += 1;
+                self.count_excluded(item_path);
+                continue;
+            }
 
-            if item_is_dir {
-                let new_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
-                collected_files.extend(self.write_tree_and_get_files(
+            if !self.config.general.hidden && FileUtils::is_hidden(item_path) {
+                self.summary.borrow_mut().skipped_filtered += 1;
+                self.count_excluded(item_path);
+                continue;
+            }
+
+            if self.is_own_artifact(item_path, item_is_dir) {
+                continue;
+            }
+
+            let display_name = item_path.file_name().unwrap().to_string_lossy();
+            if let Some(commit) = self
+                .tracked_files
+                .and_then(|tracked| tracked.submodule(item_path))
+            {
+                let mut child = TreeNode::new(&display_name, item_path, NodeKind::Submodule);
+                child.commit = Some(commit.to_string());
+                node.children.push(child);
+                continue;
+            }
+            if let Some(target) = self
+                .tracked_files
+                .and_then(|tracked| tracked.symlink_target(item_path))
+            {
+                if self.config.general.symlinks != SymlinkMode::Skip {
+                    let mut child = TreeNode::new(&display_name, item_path, NodeKind::Symlink);
+                    child.target = Some(target.to_path_buf());
+                    node.children.push(child);
+                }
+                continue;
+            }
+            let path_length = std::path::absolute(item_path)
+                .map_or(item_path.as_os_str().len(), |p| p.as_os_str().len());
+            if path_length > self.config.general.max_path_length {
+                node.children.push(TreeNode::new(
+                    &display_name,
                     item_path,
-                    output_file,
-                    depth + 1,
-                    &new_prefix,
+                    NodeKind::PathTooLong,
+                ));
+                self.warnings.borrow_mut().push(format!(
+                    "Path too long ({} bytes): {}",
+                    path_length,
+                    item_path.display()
                 ));
+                continue;
+            }
+
+            let is_symlink = item_path
+                .symlink_metadata()
+                .is_ok_and(|m| m.file_type().is_symlink());
+            if is_symlink {
+                match self.config.general.symlinks {
+                    SymlinkMode::Skip => continue,
+                    SymlinkMode::ShowAsLink => {
+                        node.children
+                            .push(Self::symlink_node(&display_name, item_path));
+                        continue;
+                    }
+                    SymlinkMode::Follow
+                        if item_is_dir && Self::is_symlink_loop(&node.path, item_path) =>
+                    {
+                        self.warnings.borrow_mut().push(format!(
+                            "Symlink loop not followed: {}",
+                            item_path.display()
+                        ));
+                        node.children
+                            .push(Self::symlink_node(&display_name, item_path));
+                        continue;
+                    }
+                    SymlinkMode::Follow => {}
+                }
+            }
+
+            let probe = (!item_is_dir).then(|| {
+                self.probe(
+                    item_path,
+                    self.config.general.binary_detection,
+                    self.annotates(),
+                )
+            });
+            let is_text = probe.is_some_and(|p| p.is_text);
+            if !item_is_dir && !is_text && !show_binary {
+                self.summary.borrow_mut().skipped_binary += 1;
+                if self.config.general.list_binary {
+                    let mut child = TreeNode::new(&display_name, item_path, NodeKind::Binary);
+                    child.size = item_path.metadata().map_or(0, |m| m.len());
+                    node.children.push(child);
+                }
+                continue;
+            }
+
+            let kind = if item_is_dir {
+                NodeKind::Directory
             } else {
-                collected_files.push(item_path.clone());
+                NodeKind::File
+            };
+            let mut child = TreeNode::new(&display_name, item_path, kind);
+            if item_is_dir {
+                self.populate_children(&mut child, depth + 1);
+                child.size = child.children.iter().map(|c| c.size).sum();
+            } else {
+                child.size = item_path.metadata().map_or(0, |m| m.len());
+                if self.annotates() && is_text {
+                    child.lines = probe.and_then(|p| p.lines);
+                }
+            }
+            node.children.push(child);
+        }
+        self.sort_children(node);
+    }
+
+    fn count_excluded(&self, path: &Path) {
+        if !self.config.general.count_excluded || gitignore::is_builtin(path) {
+            return;
+        }
+        let Ok(metadata) = path.symlink_metadata() else {
+            return;
+        };
+        if metadata.is_dir() {
+            for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+                self.count_excluded(&entry.path());
+            }
+            return;
+        }
+        if !metadata.is_file() {
+            return;
+        }
+        let probe = match self.read_cache.as_ref().and_then(|c| c.take(path)) {
+            Some(bytes) => FileUtils::probe_bytes(&bytes),
+            None => FileUtils::probe_text(path, self.throttle),
+        };
+        let lines = probe.lines.filter(|_| probe.is_text).unwrap_or(0);
+        let mut summary = self.summary.borrow_mut();
+        summary.excluded_files += 1;
+        summary.excluded_bytes += metadata.len();
+        summary.excluded_lines += lines;
+    }
+
+    fn probe(&self, path: &Path, detection: BinaryDetection, need_lines: bool) -> TextProbe {
+        let keep = self.read_cache.as_ref().is_some_and(|cache| {
+            self.shows_content(path) && cache.fits(path.metadata().map_or(0, |m| m.len()))
+        });
+        let (probe, bytes) =
+            FileUtils::detect_text(path, self.throttle, detection, need_lines, keep);
+        if let (Some(cache), Some(bytes)) = (&self.read_cache, bytes) {
+            cache.insert(path, bytes);
+        }
+        probe
+    }
+
+    fn shows_content(&self, path: &Path) -> bool {
+        let output = &self.config.output;
+        let max_file_size = output.max_file_size_for(path);
+        let start_path = Path::new(&self.config.general.path);
+        output.truncate_lines.is_some()
+            || max_file_size == 0
+            || path.metadata().is_ok_and(|m| m.len() <= max_file_size)
+            || filters::matches_any(
+                &self.config.filters.summarize_patterns,
+                &filters::relative_path(path, start_path),
+            )
+    }
+
+    fn file_marker(&self, path: &Path) -> Option<FileMarker> {
+        let Some(cache) = &self.read_cache else {
+            return FileUtils::file_marker(path, self.throttle);
+        };
+        if let Some(marker) = cache.get(path, FileUtils::marker_in) {
+            return marker;
+        }
+        let size = path.metadata().map_or(0, |m| m.len());
+        if !self.shows_content(path) || !cache.fits(size) {
+            return FileUtils::file_marker(path, self.throttle);
+        }
+        let bytes = self.throttle.read(path).ok()?;
+        let marker = FileUtils::marker_in(&bytes);
+        cache.insert(path, bytes);
+        marker
+    }
+
+    fn is_own_artifact(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+        if self.output_names.iter().any(|o| name == o.as_str()) {
+            return true;
+        }
+        let manifest = self.config.output.manifest.as_deref().map(Path::new);
+        if !is_dir && manifest.is_some_and(|m| m.file_name() == Some(name)) {
+            return true;
+        }
+        if is_dir && name == config::STATE_DIR {
+            return true;
+        }
+        let absolute = || std::path::absolute(path).ok();
+        if !is_dir && self.config.general.exclude_config {
+            let source = self
+                .config
+                .source
+                .as_ref()
+                .filter(|s| s.file_name() == Some(name));
+            if source.is_some_and(|s| std::path::absolute(s).ok() == absolute()) {
+                return true;
+            }
+        }
+        is_dir
+            && name == "projct"
+            && cache::cache_dir().is_some_and(|dir| std::path::absolute(dir).ok() == absolute())
+    }
+
+    fn sort_children(&self, node: &mut TreeNode) {
+        let (sort, reverse) = (self.config.output.sort, self.config.output.reverse);
+        if sort == SortOrder::Name && !reverse {
+            return;
+        }
+        let modified = |n: &TreeNode| n.path.symlink_metadata().and_then(|m| m.modified()).ok();
+        let extension = |n: &TreeNode| {
+            Path::new(&n.name)
+                .extension()
+                .map(|e| e.to_ascii_lowercase())
+        };
+        node.children.sort_by(|a, b| {
+            let order = match sort {
+                SortOrder::Name => std::cmp::Ordering::Equal,
+                SortOrder::Size => a.size.cmp(&b.size),
+                SortOrder::Mtime => modified(a).cmp(&modified(b)),
+                SortOrder::Extension => extension(a).cmp(&extension(b)),
+            }
+            .then_with(|| a.name.cmp(&b.name));
+            let order = if reverse { order.reverse() } else { order };
+            b.is_dir().cmp(&a.is_dir()).then(order)
+        });
+    }
+
+    fn symlink_node(name: &str, path: &Path) -> TreeNode {
+        let mut node = TreeNode::new(name, path, NodeKind::Symlink);
+        node.target = std::fs::read_link(path).ok();
+        node
+    }
+
+    fn is_symlink_loop(dir: &Path, link: &Path) -> bool {
+        match (dir.canonicalize(), link.canonicalize()) {
+            (Ok(dir), Ok(target)) => dir.starts_with(target),
+            _ => false,
+        }
+    }
+
+    fn is_filtered_out(&self, path: &Path, is_dir: bool) -> bool {
+        let ignored = !self.config.general.show_ignored
+            && self
+                .gitignore_manager
+                .is_some_and(|gm| gm.should_ignore(path, is_dir));
+        ignored
+            || self
+                .tracked_files
+                .is_some_and(|tracked| !tracked.contains(path, is_dir))
+            || self
+                .changed_files
+                .is_some_and(|changed| !changed.contains(path, is_dir))
+            || (is_dir && self.is_excluded_dir(path))
+            || (!self.config.general.hidden && FileUtils::is_hidden(path))
+            || self.is_own_artifact(path, is_dir)
+            || gitignore::is_git_dir(path)
+    }
+
+    fn is_excluded_dir(&self, dir: &Path) -> bool {
+        let rel_path = filters::relative_path(dir, Path::new(&self.config.general.path));
+        filters::excludes_dir(&self.config.filters.exclude_patterns, &rel_path)
+    }
+
+    pub fn render_tree(&self, root: &TreeNode, output_file: &mut dyn Write) {
+        let mut lines = vec![(
+            self.paint(&format!("{}/", root.name), root.kind),
+            self.annotation(root),
+        )];
+        self.tree_lines(root, "", &mut lines);
+        if self.config.output.ascii {
+            for (line, _) in &mut lines {
+                *line = ascii::to_ascii(line);
+            }
+        }
+        let column = lines
+            .iter()
+            .filter(|(_, annotation)| annotation.is_some())
+            .map(|(line, _)| visible_width(line))
+            .max()
+            .unwrap_or(0);
+        for (line, annotation) in &lines {
+            match annotation {
+                Some(annotation) => {
+                    let padding = column - visible_width(line) + 2;
+                    let _ = writeln!(output_file, "{}{:padding$}{}", line, "", annotation);
+                }
+                None => {
+                    let _ = writeln!(output_file, "{}", line);
+                }
+            }
+        }
+    }
+
+    fn paint(&self, text: &str, kind: NodeKind) -> String {
+        let color = match kind {
+            _ if !self.color => return text.to_string(),
+            NodeKind::Directory => "1;34",
+            NodeKind::Symlink => "1;36",
+            NodeKind::Binary => "2",
+            NodeKind::Unreadable | NodeKind::PathTooLong => "31",
+            _ => return text.to_string(),
+        };
+        format!("\x1b[{}m{}\x1b[0m", color, text)
+    }
+
+    fn tree_lines(&self, node: &TreeNode, prefix: &str, lines: &mut Vec<(String, Option<String>)>) {
+        let (branch, last, pipe, blank) = self.config.output.tree_style.glyphs();
+        if node.kind == NodeKind::Unreadable {
+            lines.push((format!("{}{}[Permission Denied]", prefix, last), None));
+            return;
+        }
+        if let Some(hidden) = node.hidden.filter(|&n| n > 0) {
+            let ellipsis = match self.config.output.tree_style {
+                TreeStyle::Unicode => "…",
+                _ => "...",
+            };
+            lines.push((
+                format!(
+                    "{}{}{} ({} entr{} hidden, depth limit reached)",
+                    prefix,
+                    last,
+                    ellipsis,
+                    hidden,
+                    if hidden == 1 { "y" } else { "ies" }
+                ),
+                None,
+            ));
+            return;
+        }
+        let num_children = node.children.len();
+        for (i, child) in node.children.iter().enumerate() {
+            let is_last = i == num_children - 1;
+            let connector = if is_last { last } else { branch };
+            let suffix = match (child.kind, &child.target) {
+                (NodeKind::PathTooLong, _) => " [path too long]".to_string(),
+                (NodeKind::Symlink, Some(target)) if self.config.general.reproducible => {
+                    format!(" -> {}", filters::slash_path(target))
+                }
+                (NodeKind::Symlink, Some(target)) => format!(" -> {}", target.display()),
+                (NodeKind::Symlink, None) => " [symlink]".to_string(),
+                (NodeKind::Submodule, _) => format!(
+                    " [submodule @ {}]",
+                    child
+                        .commit
+                        .as_deref()
+                        .map_or("?", |c| &c[..c.len().min(7)])
+                ),
+                (NodeKind::Binary, _) => {
+                    format!(" [binary, {}]", FileUtils::format_size(child.size))
+                }
+                _ if child.is_dir() => "/".to_string(),
+                _ => String::new(),
+            };
+            let rel_path =
+                filters::relative_path(&child.path, Path::new(&self.config.general.path));
+            let name = self
+                .config
+                .output
+                .path_aliases
+                .get(&rel_path)
+                .unwrap_or(&child.name);
+            lines.push((
+                format!(
+                    "{}{}{}",
+                    prefix,
+                    connector,
+                    self.paint(&format!("{}{}", name, suffix), child.kind)
+                ),
+                self.annotation(child),
+            ));
+            if child.is_dir() {
+                let new_prefix = format!("{}{}", prefix, if is_last { blank } else { pipe });
+                self.tree_lines(child, &new_prefix, lines);
             }
         }
+    }
+
+    fn display_path(&self, file_path: &Path, start_path: &Path) -> String {
+        let rel_path = filters::relative_path(file_path, start_path);
+        filters::apply_alias(&self.config.output.path_aliases, &rel_path)
+    }
+
+    fn commit_info(&self, file_path: &Path) -> Option<CommitInfo> {
+        if !self.config.output.commit_info
+            || self.imported.is_some()
+            || self.config.output.anonymize != AnonymizeMode::Off
+        {
+            return None;
+        }
+        CommitInfo::last_for(file_path)
+    }
 
-        collected_files
+    fn annotates(&self) -> bool {
+        self.config.output.annotate || self.config.output.anonymize != AnonymizeMode::Off
+    }
+
+    fn annotation(&self, node: &TreeNode) -> Option<String> {
+        if !self.annotates()
+            || matches!(
+                node.kind,
+                NodeKind::PathTooLong | NodeKind::Symlink | NodeKind::Binary | NodeKind::Submodule
+            )
+        {
+            return None;
+        }
+        let size = FileUtils::format_size(node.size);
+        match node.lines {
+            Some(1) => Some(format!("({}, 1 line)", size)),
+            Some(lines) => Some(format!("({}, {} lines)", size, lines)),
+            None => Some(format!("({})", size)),
+        }
     }
 
     pub fn write_file_contents(
@@ -142,135 +610,1649 @@ impl<'a> OutputWriter<'a> {
         if file_list.is_empty() {
             return;
         }
-        let max_file_size = self.config.output.max_file_size;
         let show_line_numbers = self.config.output.show_line_numbers;
-        for file_path in file_list {
-            let rel_path = file_path
-                .strip_prefix(start_path)
-                .unwrap_or(file_path)
-                .to_string_lossy();
-            let header = format!("\n{}:\n", rel_path);
-            let _ = output_file.write_all(header.as_bytes());
-            let file_size = match file_path.metadata() {
-                Ok(m) => m.len(),
-                Err(_) => 0,
-            };
-            if max_file_size > 0 && file_size > max_file_size {
-                let msg = format!("[File is too big to show ({} bytes)]\n", file_size);
-                let _ = output_file.write_all(msg.as_bytes());
-                continue;
-            }
-            let mut file = match File::open(file_path) {
-                Ok(f) => f,
-                Err(e) => {
-                    let msg = format!("[Cannot read {}: {}]\n", rel_path, e);
-                    let _ = output_file.write_all(msg.as_bytes());
+        let format = self.config.output.format;
+        let xml = format == OutputFormat::XmlDocs;
+        let mut noted_dirs = HashSet::new();
+        self.with_prefetch(file_list, |prefetched| {
+            for (index, file_path) in file_list.iter().enumerate() {
+                if let Some((dir, size)) = self.oversized_dir(file_path) {
+                    prefetched.take(index);
+                    if noted_dirs.insert(dir) {
+                        let rel_dir = self.display_path(dir, start_path);
+                        let rel_dir = match rel_dir.as_str() {
+                            "" => "./".to_string(),
+                            _ => format!("{}/", rel_dir),
+                        };
+                        let _ = output_file.write_all(self.content_header(&rel_dir).as_bytes());
+                        let _ = writeln!(output_file, "{}", self.oversized_note(*size));
+                        if xml {
+                            let _ = writeln!(output_file, "</document>");
+                        }
+                    }
                     continue;
                 }
-            };
-            let mut content = String::new();
-            if file.read_to_string(&mut content).is_err() {
-                let msg = format!("[Cannot read {}: invalid UTF-8]\n", rel_path);
-                let _ = output_file.write_all(msg.as_bytes());
-                continue;
-            }
-            if content.trim().is_empty() {
-                let _ = output_file.write_all(b"[Empty]\n");
-            } else {
-                let lines = content.lines().enumerate();
-                for (line_num, line) in lines {
-                    let out_line = if show_line_numbers {
-                        format!("{:4}: {}\n", line_num + 1, line)
-                    } else {
-                        format!("{}\n", line)
+                let rel_path = self.display_path(file_path, start_path);
+                let header = self.content_header(&rel_path);
+                if output_file.write_all(header.as_bytes()).is_err() {
+                    break;
+                }
+                if let Some(commit) = self.commit_info(file_path) {
+                    let _ = match format {
+                        OutputFormat::Markdown => writeln!(output_file, "{}\n", commit),
+                        OutputFormat::XmlDocs => {
+                            writeln!(
+                                output_file,
+                                "{}",
+                                FileUtils::xml_escape(&commit.to_string())
+                            )
+                        }
+                        _ => writeln!(output_file, "{}", commit),
+                    };
+                }
+                self.prefetched.replace(prefetched.take(index));
+                let content = match self.read_file_content(file_path, &rel_path) {
+                    Ok(c) => c,
+                    Err(msg) => {
+                        let _ = writeln!(output_file, "{}", msg);
+                        if xml {
+                            let _ = writeln!(output_file, "</document>");
+                        }
+                        continue;
+                    }
+                };
+                self.record_tokens(&rel_path, &content);
+                if content.trim().is_empty() {
+                    let _ = output_file.write_all(b"[Empty]\n");
+                } else {
+                    let fence = match format {
+                        OutputFormat::Markdown => Some(Self::markdown_fence(&content)),
+                        _ => None,
                     };
-                    let _ = output_file.write_all(out_line.as_bytes());
+                    if let Some(fence) = &fence {
+                        let language =
+                            FileUtils::fence_language(file_path, &self.config.output.language_map);
+                        let _ = writeln!(output_file, "{}{}", fence, language);
+                    }
+                    let lines = content.lines().enumerate();
+                    for (line_num, line) in lines {
+                        let escaped;
+                        let line = if xml {
+                            escaped = FileUtils::xml_escape(line);
+                            &escaped
+                        } else {
+                            line
+                        };
+                        let _ = if show_line_numbers {
+                            writeln!(output_file, "{:4}: {}", line_num + 1, line)
+                        } else {
+                            writeln!(output_file, "{}", line)
+                        };
+                    }
+                    if let Some(fence) = &fence {
+                        let _ = writeln!(output_file, "{}", fence);
+                    }
+                }
+                if xml {
+                    let _ = writeln!(output_file, "</document>");
                 }
             }
+        });
+    }
+
+    fn content_header(&self, rel_path: &str) -> String {
+        match self.config.output.format {
+            OutputFormat::Markdown => format!("\n## {}\n\n", rel_path),
+            OutputFormat::XmlDocs => {
+                self.document_index.set(self.document_index.get() + 1);
+                format!(
+                    "\n<document index=\"{}\" path=\"{}\">\n",
+                    self.document_index.get(),
+                    FileUtils::xml_escape(rel_path).replace('"', "&quot;")
+                )
+            }
+            _ => format!("\n{}:\n", rel_path),
         }
     }
-}
 
-pub struct ProjectTreeGenerator {
-    pub config: Config,
-    pub gitignore_manager: Option<HierarchicalGitignoreManager>,
-}
+    fn oversized_dir(&self, file_path: &Path) -> Option<&(PathBuf, u64)> {
+        self.oversized_dirs
+            .iter()
+            .find(|(dir, _)| file_path.starts_with(dir))
+    }
 
-impl ProjectTreeGenerator {
-    pub fn new(config: Config) -> Self {
-        let gitignore_manager = if config.general.use_gitignore {
-            Some(HierarchicalGitignoreManager::new(Path::new(
-                &config.general.path,
-            )))
-        } else {
-            None
+    fn oversized_note(&self, size: u64) -> String {
+        format!(
+            "[Contents skipped: directory holds {} of included files, over the {} limit]",
+            FileUtils::format_size(size),
+            FileUtils::format_size(
+                self.config
+                    .general
+                    .skip_content_over_dir_size
+                    .unwrap_or_default()
+            )
+        )
+    }
+
+    fn with_prefetch<R>(
+        &self,
+        file_list: &[PathBuf],
+        write: impl FnOnce(&mut Prefetched<'_>) -> R,
+    ) -> R {
+        let workers = match self.imported {
+            Some(_) => 1,
+            None => self.config.io.read_workers,
         };
-        Self {
-            config,
-            gitignore_manager,
+        let unsampled = &self.unsampled;
+        let sensitive = &self.sensitive;
+        let oversized_dirs = &self.oversized_dirs;
+        let summarized = &self.summarized;
+        let output = &self.config.output;
+        let truncating = output.truncate_lines.is_some();
+        let read_cache = self.read_cache.as_ref();
+        let wanted = |path: &Path| {
+            if unsampled.contains(path)
+                || sensitive.contains(path)
+                || read_cache.is_some_and(|cache| cache.contains(path))
+                || oversized_dirs.iter().any(|(dir, _)| path.starts_with(dir))
+            {
+                return false;
+            }
+            let max_file_size = output.max_file_size_for(path);
+            truncating
+                || summarized.contains(path)
+                || max_file_size == 0
+                || path.metadata().is_ok_and(|m| m.len() <= max_file_size)
+        };
+        prefetch::run(self.throttle, file_list, workers, wanted, write)
+    }
+
+    pub fn read_file_content(&self, file_path: &Path, rel_path: &str) -> Result<String, String> {
+        let content = self.read_redacted(file_path, rel_path);
+        if let (Some(detector), Ok(content)) = (self.license_detector, &content) {
+            for license in detector.detect(content) {
+                self.licenses
+                    .borrow_mut()
+                    .entry(license)
+                    .or_default()
+                    .push(rel_path.to_string());
+            }
+        }
+        self.progress.emit(ProgressEvent::FileProcessed {
+            path: file_path.to_path_buf(),
+            bytes: content.as_ref().map_or(0, |c| c.len() as u64),
+        });
+        if self.config.output.manifest.is_some() {
+            self.record_manifest(file_path, rel_path, &content);
         }
+        content
     }
 
-    pub fn generate(&self) {
-        let output_filename = self.config.output.filename.clone();
-        let start_path = Path::new(&self.config.general.path);
-        let mut output_file = match OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&output_filename)
+    fn record_manifest(&self, file_path: &Path, rel_path: &str, content: &Result<String, String>) {
+        let size = file_path.metadata().map_or(0, |m| m.len());
+        let entry = match content {
+            Ok(content) => ManifestEntry {
+                path: rel_path.to_string(),
+                size,
+                lines: Some(content.lines().count()),
+                sha256: self.last_digest.take(),
+                skipped: None,
+            },
+            Err(message) => ManifestEntry::skipped(
+                rel_path,
+                size,
+                message.trim_start_matches('[').trim_end_matches(']'),
+            ),
+        };
+        self.manifest.borrow_mut().push(entry);
+    }
+
+    fn read_redacted(&self, file_path: &Path, rel_path: &str) -> Result<String, String> {
+        self.last_read.set(Duration::ZERO);
+        self.last_digest.take();
+        let mut content = self.read_source(file_path, rel_path)?;
+        if let Some(redactor) = self.redactor {
+            let (redacted, count) = redactor.redact(file_path, &content);
+            if count > 0 {
+                self.warnings.borrow_mut().push(format!(
+                    "Redacted {} secret{} in {}",
+                    count,
+                    if count == 1 { "" } else { "s" },
+                    rel_path
+                ));
+            }
+            content = redacted;
+        }
+        for transform in self.transforms {
+            content = transform.transform(file_path, content);
+        }
+        let mut summary = self.summary.borrow_mut();
+        summary.files_included += 1;
+        summary.bytes += content.len() as u64;
+        summary.lines += content.lines().count();
+        Ok(content)
+    }
+
+    fn read_source(&self, file_path: &Path, rel_path: &str) -> Result<String, String> {
+        let read_started = Instant::now();
+        let prefetched = self.prefetched.take();
+        if self.unsampled.contains(file_path) {
+            return Err("[Not sampled]".to_string());
+        }
+        if self.sensitive.contains(file_path) {
+            return Err("[Excluded: sensitive file, use --no-default-excludes]".to_string());
+        }
+        if let Some(imported) = &self.imported {
+            return match imported.get(file_path) {
+                Some(file) => file.content.clone(),
+                None => Err(format!("[Cannot read {}: not in imported tree]", rel_path)),
+            };
+        }
+        let max_file_size = self.config.output.max_file_size_for(file_path);
+        let metadata = file_path.metadata().ok();
+        if let Some(identity) = metadata.as_ref().and_then(FileUtils::hard_link_identity) {
+            let mut seen_inodes = self.seen_inodes.borrow_mut();
+            if let Some(original) = seen_inodes.get(&identity) {
+                return Err(format!("[Hard link to {}]", original));
+            }
+            seen_inodes.insert(identity, rel_path.to_string());
+        }
+        let file_size = metadata.map_or(0, |m| m.len());
+        let too_big = max_file_size > 0 && file_size > max_file_size;
+        let truncate_lines = self.config.output.truncate_lines.filter(|_| too_big);
+        let summarize = self.summarized.contains(file_path);
+        if too_big && truncate_lines.is_none() && !summarize {
+            self.summary.borrow_mut().skipped_too_big += 1;
+            return Err(format!("[File is too big to show ({} bytes)]", file_size));
+        }
+        let cached = self.read_cache.as_ref().and_then(|c| c.take(file_path));
+        let bytes = match cached
+            .map(Ok)
+            .or(prefetched)
+            .unwrap_or_else(|| self.throttle.read(file_path))
         {
-            Ok(f) => f,
-            Err(e) => {
-                println!("Cannot open output file: {}", e);
-                return;
+            Ok(bytes) => bytes,
+            Err(e) if FileUtils::is_locked_error(&e) => {
+                return Err(format!(
+                    "[Locked by another program ({})]",
+                    FileUtils::format_size(file_size)
+                ));
             }
+            Err(e) => return Err(format!("[Cannot read {}: {}]", rel_path, e)),
         };
-        let output_writer = OutputWriter {
-            config: &self.config,
-            gitignore_manager: self.gitignore_manager.as_ref(),
+        self.last_read.set(read_started.elapsed());
+        if self.config.output.manifest.is_some() {
+            self.last_digest.replace(Some(sha256::hex_digest(&bytes)));
+        }
+        if summarize {
+            self.summary.borrow_mut().skipped_filtered += 1;
+            let lines = bytes.iter().filter(|&&b| b == b'\n').count();
+            return Err(format!(
+                "[Summarized: {} line{}, {}]",
+                FileUtils::format_count(lines),
+                if lines == 1 { "" } else { "s" },
+                FileUtils::format_size(file_size)
+            ));
+        }
+        let entropy_threshold = self.config.output.entropy_threshold;
+        if entropy_threshold > 0.0
+            && bytes.len() >= MIN_ENTROPY_SAMPLE
+            && FileUtils::entropy(&bytes) > entropy_threshold
+        {
+            self.warnings
+                .borrow_mut()
+                .push(format!("Skipped high-entropy content: {}", rel_path));
+            self.summary.borrow_mut().skipped_binary += 1;
+            return Err("[high entropy, likely binary/encrypted]".to_string());
+        }
+        let Some((mut content, encoding)) = FileUtils::decode_text(bytes) else {
+            return Err(format!("[Cannot read {}: invalid UTF-8]", rel_path));
         };
+        if let Some(encoding) = encoding {
+            self.warnings
+                .borrow_mut()
+                .push(format!("Decoded {} from {}", rel_path, encoding));
+        }
+        if let Some(TruncateLines { head, tail }) = truncate_lines {
+            if let Some(truncated) = FileUtils::truncate_lines(&content, head, tail) {
+                self.warnings.borrow_mut().push(format!(
+                    "Truncated {} to its first {} and last {} lines",
+                    rel_path, head, tail
+                ));
+                content = truncated;
+            }
+        }
+        if let Some(pointer) = LfsPointer::parse(&content) {
+            content = self.resolve_lfs_pointer(&pointer, file_path)?;
+        }
+        let content = FileUtils::strip_ignored_regions(&content);
+        let content =
+            FileUtils::process_front_matter(file_path, &content, self.config.output.front_matter);
+        let language = FileUtils::fence_language(file_path, &self.config.output.language_map);
+        if self.config.output.docs_only && !docs::is_readme(file_path) {
+            return match docs::extract(language, &content) {
+                Some(docs) if !docs.is_empty() => Ok(docs),
+                _ => {
+                    self.summary.borrow_mut().skipped_filtered += 1;
+                    Err("[No doc comments]".to_string())
+                }
+            };
+        }
+        if !self.config.transform.strip_comments {
+            return Ok(content);
+        }
+        Ok(comments::strip(language, &content).unwrap_or(content))
+    }
 
-        let root_display_name = start_path
-            .canonicalize()
-            .unwrap_or(start_path.to_path_buf())
-            .file_name()
-            .unwrap_or_else(|| std::ffi::OsStr::new("."))
-            .to_string_lossy()
-            .to_string();
-        let _ = writeln!(&mut output_file, "{}/", root_display_name);
+    fn resolve_lfs_pointer(
+        &self,
+        pointer: &LfsPointer,
+        file_path: &Path,
+    ) -> Result<String, String> {
+        if pointer.size > self.config.output.lfs_fetch_max_size {
+            return Err(pointer.describe());
+        }
+        let Some(object) = pointer.local_object(file_path) else {
+            return Err(pointer.describe());
+        };
+        let mut file = self
+            .throttle
+            .open(&object)
+            .map_err(|_| pointer.describe())?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)
+            .map_err(|_| pointer.describe())?;
+        Ok(content)
+    }
 
-        let mut file_list =
-            output_writer.write_tree_and_get_files(start_path, &mut output_file, 0, "");
+    pub fn build_json_output(
+        &self,
+        file_list: &[PathBuf],
+        start_path: &Path,
+        root_name: &str,
+    ) -> JsonOutput {
+        let mut root = JsonNode::directory(root_name);
+        let mut slowest = vec![];
+        self.with_prefetch(file_list, |prefetched| {
+            for (index, file_path) in file_list.iter().enumerate() {
+                let rel_path = self.display_path(file_path, start_path);
+                let components: Vec<String> = rel_path
+                    .split('/')
+                    .filter(|c| !c.is_empty() && *c != ".")
+                    .map(str::to_string)
+                    .collect();
+                let Some((name, dirs)) = components.split_last() else {
+                    prefetched.take(index);
+                    continue;
+                };
+                let size = match &self.imported {
+                    Some(imported) => imported.get(file_path).map_or(0, |f| f.size),
+                    None => file_path.metadata().map(|m| m.len()).unwrap_or(0),
+                };
+                let started = Instant::now();
+                let (content, note) = match self.oversized_dir(file_path) {
+                    Some((_, dir_size)) => {
+                        prefetched.take(index);
+                        (None, Some(self.oversized_note(*dir_size)))
+                    }
+                    None => {
+                        self.prefetched.replace(prefetched.take(index));
+                        match self.read_file_content(file_path, &rel_path) {
+                            Ok(c) => (Some(c), None),
+                            Err(msg) => (None, Some(msg)),
+                        }
+                    }
+                };
+                let tokens = content
+                    .as_ref()
+                    .and_then(|c| self.record_tokens(&rel_path, c));
+                let read = self.last_read.get();
+                let transform = started.elapsed().saturating_sub(read);
+                let mut node = JsonNode::File {
+                    name: name.clone(),
+                    path: rel_path.clone(),
+                    size,
+                    content,
+                    note,
+                    tokens,
+                    timings: None,
+                    commit: self.commit_info(file_path),
+                };
+                if self.config.output.timings {
+                    let write_started = Instant::now();
+                    let _ = serde_json::to_writer(io::sink(), &node);
+                    let timings = FileTimings::new(read, transform, write_started.elapsed());
+                    slowest.push(SlowFile {
+                        path: rel_path,
+                        total_ms: timings.total_ms(),
+                    });
+                    if let JsonNode::File { timings: t, .. } = &mut node {
+                        *t = Some(timings);
+                    }
+                }
+                root.insert(dirs, node);
+            }
+        });
+        slowest.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+        slowest.truncate(SLOWEST_FILES);
+        JsonOutput {
+            root,
+            slowest,
+            summary: (self.config.output.summary == SummaryMode::Append)
+                .then(|| *self.summary.borrow()),
+            total_tokens: self
+                .token_counter
+                .map(|_| self.token_counts.borrow().iter().map(|(_, t)| t).sum()),
+            licenses: self.licenses.borrow().clone(),
+            warnings: self.warnings.borrow().clone(),
+        }
+    }
 
-        file_list = self.filter_file_list(file_list);
+    fn record_tokens(&self, rel_path: &str, content: &str) -> Option<usize> {
+        let tokens = self.token_counter?.count(content);
+        self.token_counts
+            .borrow_mut()
+            .push((rel_path.to_string(), tokens));
+        Some(tokens)
+    }
 
-        output_writer.write_file_contents(&file_list, &mut output_file, start_path);
+    pub fn write_token_summary(&self, output_file: &mut dyn Write) {
+        let Some(counter) = self.token_counter else {
+            return;
+        };
+        let mut counts = self.token_counts.borrow().clone();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let total: usize = counts.iter().map(|(_, t)| t).sum();
+        let width = total.to_string().len().max("tokens".len());
+        let _ = writeln!(output_file, "\nToken summary ({}):", counter.name());
+        let _ = writeln!(output_file, "{:>width$}  file", "tokens");
+        for (path, tokens) in &counts {
+            let _ = writeln!(output_file, "{:>width$}  {}", tokens, path);
+        }
+        let _ = writeln!(output_file, "{:>width$}  total", total);
     }
 
-    fn filter_file_list(&self, mut file_list: Vec<PathBuf>) -> Vec<PathBuf> {
-        let include_patterns = &self.config.filters.include_patterns;
+    pub fn write_license_summary(&self, output_file: &mut dyn Write) {
+        let licenses = self.licenses.borrow();
+        if licenses.is_empty() {
+            return;
+        }
+        let _ = writeln!(output_file, "\nLicenses:");
+        for (license, files) in licenses.iter() {
+            let _ = writeln!(
+                output_file,
+                "- {} ({} file{}): {}",
+                license,
+                files.len(),
+                if files.len() == 1 { "" } else { "s" },
+                files.join(", ")
+            );
+        }
+    }
+
+    fn markdown_fence(content: &str) -> String {
+        let mut longest_run = 0;
+        let mut run = 0;
+        for c in content.chars() {
+            if c == '`' {
+                run += 1;
+                longest_run = longest_run.max(run);
+            } else {
+                run = 0;
+            }
+        }
+        "`".repeat(longest_run.max(2) + 1)
+    }
+}
+
+pub struct ProjectTreeGenerator {
+    pub config: Config,
+    gitignore_manager: Option<HierarchicalGitignoreManager>,
+    tracked_files: Option<Result<GitPathSet, String>>,
+    changed_files: Option<Result<GitPathSet, String>>,
+    throttle: IoThrottle,
+    token_counter: Option<TokenCounter>,
+    redactor: Option<Redactor>,
+    license_detector: Option<LicenseDetector>,
+    permission_filter: Option<PermissionFilter>,
+    progress: Progress,
+    transforms: Vec<Arc<dyn ContentTransform>>,
+    summary: RefCell<RunSummary>,
+    manifest: RefCell<Vec<ManifestEntry>>,
+}
+
+impl ProjectTreeGenerator {
+    pub fn new(mut config: Config) -> Self {
+        config.expand_groups();
+        config.pin_reproducible();
+        let gitignore_manager = if config.general.use_gitignore {
+            Some(HierarchicalGitignoreManager::new(
+                Path::new(&config.general.path),
+                &config.general.extra_ignore_files,
+            ))
+        } else {
+            None
+        };
+        let tracked_files = (config.general.git_tracked && config.general.from_json.is_none())
+            .then(|| GitPathSet::tracked(Path::new(&config.general.path)));
+        let changed_files = config
+            .general
+            .changed_since
+            .as_deref()
+            .filter(|_| config.general.from_json.is_none())
+            .map(|git_ref| GitPathSet::changed_since(Path::new(&config.general.path), git_ref));
+        let throttle = IoThrottle::new(config.io.max_open_files, config.io.bytes_per_sec);
+        let token_counter = config
+            .output
+            .tokens
+            .then(|| TokenCounter::new(config.output.tokenizer));
+        let redactor = Redactor::new(&config.redact);
+        let license_detector = config.output.licenses.then(LicenseDetector::default);
+        let permission_filter = PermissionFilter::new(&config.filters).ok().flatten();
+        Self {
+            config,
+            gitignore_manager,
+            tracked_files,
+            changed_files,
+            throttle,
+            token_counter,
+            redactor,
+            license_detector,
+            permission_filter,
+            progress: Progress::default(),
+            transforms: vec![],
+            summary: RefCell::new(RunSummary::default()),
+            manifest: RefCell::new(vec![]),
+        }
+    }
+
+    pub fn add_transform(&mut self, transform: impl ContentTransform + 'static) {
+        self.transforms.push(Arc::new(transform));
+    }
+
+    pub fn on_progress(&self, callback: impl FnMut(ProgressEvent) + Send + 'static) {
+        self.progress.set_callback(callback);
+    }
+
+    pub fn summary(&self) -> RunSummary {
+        *self.summary.borrow()
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.gitignore_manager
+            .as_ref()
+            .is_some_and(|gm| gm.should_ignore(path, is_dir))
+    }
+
+    pub fn is_tracked(&self, path: &Path, is_dir: bool) -> bool {
+        match &self.tracked_files {
+            Some(Ok(tracked)) => tracked.contains(path, is_dir),
+            Some(Err(_)) => false,
+            None => true,
+        }
+    }
+
+    pub fn generate(&self) -> error::Result<()> {
+        let started = Instant::now();
+        let mut filename = self.config.output.filename.clone();
+        let result = self.run_generation(&mut filename);
+        if result.is_ok() && self.config.output.summary != SummaryMode::Off {
+            eprintln!("{}", self.summary().exit_line(&filename, started.elapsed()));
+        }
+        notifications::send(
+            &self.config,
+            &Completion {
+                output: &filename,
+                summary: self.summary(),
+                elapsed: started.elapsed(),
+                error: result.as_ref().err().map(ToString::to_string),
+            },
+        );
+        result
+    }
+
+    fn run_generation(&self, filename: &mut String) -> error::Result<()> {
+        self.summary.take();
+        self.manifest.take();
+        self.progress.reset();
+        let imported = self.load_import()?;
+        if self.config.output.filename == "auto" {
+            let root_name = match &imported {
+                Some(imported) => imported.tree.root.name.clone(),
+                None => self.root_display_name(),
+            };
+            *filename = naming::auto_filename(
+                &root_name,
+                self.config.output.format,
+                !self.config.general.reproducible,
+            );
+        }
+        let filename = filename.as_str();
+        hooks::run(&self.config, "pre", filename)?;
+        if self.config.output.split_size.is_some() || self.config.output.split_tokens.is_some() {
+            self.generate_parts(filename, imported)?;
+        } else {
+            self.generate_output(filename, imported)?;
+        }
+        if let Some(path) = &self.config.output.manifest {
+            manifest::write(Path::new(path), &self.manifest.borrow()).map_err(|source| {
+                Error::Output {
+                    path: path.into(),
+                    source,
+                }
+            })?;
+        }
+        if self.config.output.summary == SummaryMode::Full {
+            eprint!("{}", self.summary());
+        }
+        hooks::run(&self.config, "post", filename)
+    }
+
+    fn generate_output(&self, filename: &str, imported: Option<ImportedTree>) -> error::Result<()> {
+        let mut namer = OutputNamer::new(filename, &self.config.output.name_template);
+        let to_stdout = self.config.output.filename == "-";
+        let output_path = if self.config.output.filename.is_empty() || to_stdout {
+            None
+        } else {
+            Some(namer.claim(&OutputName::default())?)
+        };
+        let output_names = namer.claimed_names();
+        let output_error = |source| Error::Output {
+            path: output_path.clone().unwrap_or_else(|| PathBuf::from("-")),
+            source,
+        };
+        let check_write = |result: io::Result<()>| match result {
+            Err(source) if source.kind() != io::ErrorKind::BrokenPipe => Err(output_error(source)),
+            _ => Ok(()),
+        };
+
+        let write_buffer_size = self.config.io.write_buffer_size;
+        let stdout = || BufWriter::with_capacity(write_buffer_size, io::stdout().lock());
+        if to_stdout && !self.config.output.clipboard {
+            return check_write(self.write_output(&mut stdout(), output_names, imported));
+        }
+
+        let mut buffer = SpillBuffer::new(self.config.io.max_buffer_size, write_buffer_size);
+        check_write(self.write_output(&mut buffer, output_names, imported))?;
+        if buffer.is_spilled() {
+            eprintln!(
+                "Output is larger than io.max_buffer_size; buffered {} in a temporary file",
+                FileUtils::format_size(buffer.len())
+            );
+        }
+        if to_stdout {
+            check_write(buffer.reader().and_then(|mut reader| {
+                let mut stdout = stdout();
+                io::copy(&mut reader, &mut stdout)?;
+                stdout.flush()
+            }))?;
+        }
+        if let Some(path) = &output_path {
+            if self.config.output.append {
+                self.append_to(path, &mut buffer).map_err(output_error)?;
+            } else if !self
+                .write_if_changed(path, &mut buffer)
+                .map_err(output_error)?
+            {
+                eprintln!("{} unchanged", path.display());
+            }
+        }
+        if self.config.output.clipboard {
+            let mut bytes = vec![];
+            check_write(
+                buffer
+                    .reader()
+                    .and_then(|mut reader| reader.read_to_end(&mut bytes))
+                    .map(|_| ()),
+            )?;
+            let backend =
+                clipboard::copy(&String::from_utf8_lossy(&bytes)).map_err(Error::Clipboard)?;
+            eprintln!("Copied {} bytes to clipboard ({})", bytes.len(), backend);
+        }
+        Ok(())
+    }
+
+    pub fn write_tree_only(&self, output_file: &mut dyn Write, color: bool) -> error::Result<()> {
+        let roots = self.config.roots();
+        if let Some(missing) = roots.iter().find(|root| !Path::new(root).exists()) {
+            return Err(Error::StartPath {
+                path: missing.into(),
+            });
+        }
+        let (mut directories, mut files) = (0, 0);
+        let mut write = || -> io::Result<()> {
+            for (i, root) in roots.iter().enumerate() {
+                let mut config = self.config.clone();
+                config.general.path = root.clone();
+                config.general.paths = vec![];
+                config.general.hidden |= config.general.show_ignored;
+                let generator = ProjectTreeGenerator::new(config);
+                let mut output_writer = generator.output_writer(vec![]);
+                output_writer.color = color;
+                let tree =
+                    output_writer.build_tree(Path::new(root), &generator.root_display_name());
+                if i > 0 {
+                    writeln!(output_file)?;
+                }
+                output_writer.render_tree(&tree.root, output_file);
+                for child in &tree.root.children {
+                    child.walk(&mut |node| {
+                        if node.is_dir() {
+                            directories += 1;
+                        } else {
+                            files += 1;
+                        }
+                    });
+                }
+            }
+            writeln!(
+                output_file,
+                "\n{} director{}, {} file{}",
+                directories,
+                if directories == 1 { "y" } else { "ies" },
+                files,
+                if files == 1 { "" } else { "s" }
+            )?;
+            output_file.flush()
+        };
+        write().map_err(|source| Error::Output {
+            path: PathBuf::from("-"),
+            source,
+        })
+    }
+
+    pub fn write_to(&self, output_file: &mut dyn Write) -> error::Result<()> {
+        let imported = self.load_import()?;
+        self.summary.take();
+        self.progress.reset();
+        self.write_output(output_file, vec![], imported)
+            .map_err(|source| Error::Output {
+                path: PathBuf::from("-"),
+                source,
+            })
+    }
+
+    fn load_import(&self) -> error::Result<Option<ImportedTree>> {
+        let multiple_roots = !self.config.general.paths.is_empty();
+        if let Some(json_path) = &self.config.general.from_json {
+            if multiple_roots {
+                return Err(Error::Usage(
+                    "--from-json cannot be combined with multiple paths".into(),
+                ));
+            }
+            return Ok(Some(json::import(Path::new(json_path))?));
+        }
+        if let Some(missing) = self
+            .config
+            .roots()
+            .iter()
+            .find(|root| !Path::new(root).exists())
+        {
+            return Err(Error::StartPath {
+                path: missing.into(),
+            });
+        }
+        if self.config.general.git_tracked {
+            let tracked_error = |message: &str| Error::Usage(format!("--git-tracked: {}", message));
+            if let Some(Err(message)) = &self.tracked_files {
+                return Err(tracked_error(message));
+            }
+            for root in self.config.roots().iter().skip(1) {
+                GitPathSet::tracked(Path::new(root)).map_err(|m| tracked_error(&m))?;
+            }
+        }
+        if let Some(git_ref) = &self.config.general.changed_since {
+            let changed_error =
+                |message: &str| Error::Usage(format!("--changed-since {}: {}", git_ref, message));
+            if let Some(Err(message)) = &self.changed_files {
+                return Err(changed_error(message));
+            }
+            for root in self.config.roots().iter().skip(1) {
+                GitPathSet::changed_since(Path::new(root), git_ref)
+                    .map_err(|m| changed_error(&m))?;
+            }
+        }
+        Ok(None)
+    }
+
+    fn generate_parts(&self, filename: &str, imported: Option<ImportedTree>) -> error::Result<()> {
+        let output = &self.config.output;
+        if !self.config.general.paths.is_empty() {
+            return Err(Error::Usage(
+                "--split-size and --split-tokens cannot be combined with multiple paths".into(),
+            ));
+        }
+        if output.append {
+            return Err(Error::Usage(
+                "--split-size and --split-tokens cannot be combined with --append".into(),
+            ));
+        }
+        if output.format == OutputFormat::Json || output.anonymize != AnonymizeMode::Off {
+            return Err(Error::Usage(
+                "--split-size and --split-tokens only work with text or markdown output".into(),
+            ));
+        }
+        if filename.is_empty() || filename == "-" || output.clipboard {
+            return Err(Error::Usage(
+                "--split-size and --split-tokens need an output file".into(),
+            ));
+        }
+        let mut namer = OutputNamer::new(filename, &output.name_template);
+        let part_name = |part| OutputName {
+            part: Some(part),
+            ..Default::default()
+        };
+        let output_names = (1..)
+            .map(|part| namer.render(&part_name(part)))
+            .take_while(|path| path.exists())
+            .filter_map(|path| path.file_name().map(|n| n.to_string_lossy().to_string()))
+            .collect();
+        let mut parts = self.split_report(output_names, imported);
+        if let Some(limit) = output.max_total_size {
+            let mut total = 0;
+            let fitting = parts
+                .iter()
+                .take_while(|part| {
+                    total += part.len() as u64;
+                    total <= limit
+                })
+                .count();
+            if fitting < parts.len() {
+                if !output.truncate_at_limit || fitting == 0 {
+                    return Err(Error::Output {
+                        path: namer.render(&part_name(fitting + 1)),
+                        source: limit::limit_error(limit),
+                    });
+                }
+                eprintln!(
+                    "Kept {} of {} parts within output.max_total_size",
+                    fitting,
+                    parts.len()
+                );
+                parts.truncate(fitting);
+            }
+        }
+        let mut unchanged = 0;
+        for (i, part) in parts.iter().enumerate() {
+            let path = namer.claim(&part_name(i + 1))?;
+            let mut encoded = SpillBuffer::new(
+                self.config.io.max_buffer_size,
+                self.config.io.write_buffer_size,
+            );
+            let changed = self
+                .write_bytes(&mut encoded, part)
+                .and_then(|()| self.write_if_changed(&path, &mut encoded))
+                .map_err(|source| Error::Output { path, source })?;
+            if !changed {
+                unchanged += 1;
+            }
+        }
+        if unchanged > 0 {
+            eprintln!(
+                "Split output into {} parts ({} unchanged)",
+                parts.len(),
+                unchanged
+            );
+        } else {
+            eprintln!("Split output into {} parts", parts.len());
+        }
+        Ok(())
+    }
+
+    fn write_if_changed(&self, path: &Path, buffer: &mut SpillBuffer) -> io::Result<bool> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        lock::lock_exclusive(&file, path)?;
+        if file.metadata()?.len() == buffer.len() {
+            if Self::same_contents(&mut file, &mut buffer.reader()?)? {
+                return Ok(false);
+            }
+            file.seek(SeekFrom::Start(0))?;
+        }
+        file.set_len(0)?;
+        let mut writer = BufWriter::with_capacity(self.config.io.write_buffer_size, &mut file);
+        io::copy(&mut buffer.reader()?, &mut writer)?;
+        writer.flush()?;
+        Ok(true)
+    }
+
+    fn append_to(&self, path: &Path, buffer: &mut SpillBuffer) -> io::Result<()> {
+        let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+        lock::lock_exclusive(&file, path)?;
+        let mut writer = BufWriter::with_capacity(self.config.io.write_buffer_size, &mut file);
+        io::copy(&mut buffer.reader()?, &mut writer)?;
+        writer.flush()
+    }
+
+    fn same_contents(a: &mut dyn Read, b: &mut dyn Read) -> io::Result<bool> {
+        let mut chunk_a = vec![0; 64 * 1024];
+        let mut chunk_b = vec![0; 64 * 1024];
+        loop {
+            let n = a.read(&mut chunk_a)?;
+            if n == 0 {
+                return Ok(b.read(&mut chunk_b[..1])? == 0);
+            }
+            if b.read_exact(&mut chunk_b[..n]).is_err() || chunk_a[..n] != chunk_b[..n] {
+                return Ok(false);
+            }
+        }
+    }
+
+    fn write_bytes(&self, output_file: &mut dyn Write, bytes: &[u8]) -> io::Result<()> {
+        let output_file = &mut ProgressWriter::new(output_file, &self.progress);
+        if !self.config.output.ascii {
+            output_file.write_all(bytes)?;
+            return output_file.flush();
+        }
+        let mut ascii_writer = AsciiWriter::new(output_file);
+        ascii_writer.write_all(bytes)?;
+        ascii_writer.finish()
+    }
+
+    pub fn build_tree(&self) -> ProjectTree {
+        let output_names = Path::new(&self.config.output.filename)
+            .file_name()
+            .map(|n| vec![n.to_string_lossy().to_string()])
+            .unwrap_or_default();
+        let start_path = Path::new(&self.config.general.path);
+        self.output_writer(output_names)
+            .build_tree(start_path, &self.root_display_name())
+    }
+
+    pub fn content_paths(&self) -> Vec<PathBuf> {
+        let tree = self.build_tree();
+        let mut file_list = self.filter_file_list(tree.files(), None);
+        let sensitive = self.sensitive_files(&file_list);
+        file_list.retain(|f| !sensitive.contains(f));
+        self.order_contents(&tree, &mut file_list, Path::new(&self.config.general.path));
+        file_list
+    }
+
+    fn order_contents(&self, tree: &ProjectTree, file_list: &mut [PathBuf], start_path: &Path) {
+        match self.config.output.content_order {
+            ContentOrder::Tree => {}
+            ContentOrder::Size => {
+                let mut sizes = HashMap::new();
+                tree.root.walk(&mut |node| {
+                    sizes.insert(node.path.as_path(), node.size);
+                });
+                file_list.sort_by_key(|f| std::cmp::Reverse(sizes.get(f.as_path()).copied()));
+            }
+            ContentOrder::Mtime => file_list.sort_by_cached_key(|f| {
+                std::cmp::Reverse(f.metadata().and_then(|m| m.modified()).ok())
+            }),
+            ContentOrder::Alpha => {
+                file_list.sort_by_cached_key(|f| filters::relative_path(f, start_path))
+            }
+        }
+        let group_order = &self.config.output.group_order;
+        if !group_order.is_empty() {
+            file_list.sort_by_cached_key(|f| {
+                let rel_path = filters::relative_path(f, start_path);
+                group_order
+                    .iter()
+                    .position(|g| filters::matches_any(self.config.group_patterns(g), &rel_path))
+                    .unwrap_or(group_order.len())
+            });
+        }
+    }
+
+    fn output_writer(&self, output_names: Vec<String>) -> OutputWriter<'_> {
+        OutputWriter {
+            config: &self.config,
+            gitignore_manager: self.gitignore_manager.as_ref(),
+            tracked_files: self
+                .tracked_files
+                .as_ref()
+                .and_then(|tracked| tracked.as_ref().ok()),
+            changed_files: self
+                .changed_files
+                .as_ref()
+                .filter(|_| !self.config.general.full_tree)
+                .and_then(|changed| changed.as_ref().ok()),
+            throttle: &self.throttle,
+            token_counter: self.token_counter.as_ref(),
+            redactor: self.redactor.as_ref(),
+            transforms: &self.transforms,
+            license_detector: self.license_detector.as_ref(),
+            licenses: RefCell::new(BTreeMap::new()),
+            output_names,
+            warnings: RefCell::new(vec![]),
+            seen_inodes: RefCell::new(HashMap::new()),
+            token_counts: RefCell::new(vec![]),
+            imported: None,
+            unsampled: HashSet::new(),
+            sensitive: HashSet::new(),
+            summarized: HashSet::new(),
+            oversized_dirs: vec![],
+            last_read: Cell::new(Duration::ZERO),
+            prefetched: RefCell::new(None),
+            read_cache: None,
+            last_digest: RefCell::new(None),
+            document_index: Cell::new(0),
+            color: false,
+            summary: RefCell::new(RunSummary::default()),
+            manifest: RefCell::new(vec![]),
+            progress: &self.progress,
+        }
+    }
+
+    fn write_output(
+        &self,
+        output_file: &mut dyn Write,
+        output_names: Vec<String>,
+        imported: Option<ImportedTree>,
+    ) -> io::Result<()> {
+        let Some(limit) = self.config.output.max_total_size else {
+            return self.write_encoded(output_file, output_names, imported);
+        };
+        let mut limited =
+            LimitWriter::new(output_file, limit, self.config.output.truncate_at_limit);
+        let result = self.write_encoded(&mut limited, output_names, imported);
+        self.summary.borrow_mut().truncated_size = limited.truncated_size();
+        result
+    }
+
+    fn write_encoded(
+        &self,
+        output_file: &mut dyn Write,
+        output_names: Vec<String>,
+        imported: Option<ImportedTree>,
+    ) -> io::Result<()> {
+        let output_file = &mut ProgressWriter::new(output_file, &self.progress);
+        if !self.config.output.ascii {
+            return self.write_report(output_file, output_names, imported);
+        }
+        let mut ascii_writer = AsciiWriter::new(output_file);
+        self.write_report(&mut ascii_writer, output_names, imported)?;
+        ascii_writer.finish()
+    }
+
+    fn write_report(
+        &self,
+        output_file: &mut dyn Write,
+        output_names: Vec<String>,
+        imported: Option<ImportedTree>,
+    ) -> io::Result<()> {
+        if !self.config.general.paths.is_empty() {
+            return self.write_roots(output_file, output_names);
+        }
+        if self.config.output.format == OutputFormat::Json {
+            let json_output = self.json_report(output_names, imported);
+            serde_json::to_writer_pretty(&mut *output_file, &json_output)?;
+            writeln!(output_file)?;
+            return output_file.flush();
+        }
+
+        let mut output_writer = self.output_writer(output_names);
+        let (mut tree, start_path) = self.load_tree(&mut output_writer, imported);
+        if self.config.output.anonymize != AnonymizeMode::Off {
+            tree.anonymize(self.config.output.anonymize);
+            let file_count = || tree.files().len();
+            self.write_header(output_file, &tree.root.name, file_count)?;
+            self.write_tree(&output_writer, &tree, output_file);
+            self.write_trailer(output_file, &tree.root.name, file_count)?;
+            self.record_summary(&output_writer);
+            return output_file.flush();
+        }
+
+        let file_list = self.content_files(&mut output_writer, &tree, start_path);
+        self.write_header(output_file, &tree.root.name, || file_list.len())?;
+        self.write_tree(&output_writer, &tree, output_file);
+        output_writer.write_file_contents(&file_list, output_file, start_path);
+        self.write_footer(&output_writer, output_file);
+        self.write_trailer(output_file, &tree.root.name, || file_list.len())?;
+        self.record_summary(&output_writer);
+        output_file.flush()
+    }
+
+    fn json_report(&self, output_names: Vec<String>, imported: Option<ImportedTree>) -> JsonOutput {
+        let mut output_writer = self.output_writer(output_names);
+        let (mut tree, start_path) = self.load_tree(&mut output_writer, imported);
+        if self.config.output.anonymize != AnonymizeMode::Off {
+            tree.anonymize(self.config.output.anonymize);
+            self.record_summary(&output_writer);
+            return JsonOutput {
+                root: JsonNode::from_tree(&tree.root),
+                total_tokens: None,
+                slowest: vec![],
+                summary: None,
+                licenses: BTreeMap::new(),
+                warnings: vec![],
+            };
+        }
+        let file_list = self.content_files(&mut output_writer, &tree, start_path);
+        let json_output = output_writer.build_json_output(&file_list, start_path, &tree.root.name);
+        self.record_summary(&output_writer);
+        json_output
+    }
+
+    fn write_roots(
+        &self,
+        output_file: &mut dyn Write,
+        output_names: Vec<String>,
+    ) -> io::Result<()> {
+        let generators: Vec<ProjectTreeGenerator> = self
+            .config
+            .roots()
+            .into_iter()
+            .map(|root| {
+                let mut config = self.config.clone();
+                config.general.path = root;
+                config.general.paths = vec![];
+                config.output.header = None;
+                config.output.footer = None;
+                let mut generator = ProjectTreeGenerator::new(config);
+                generator.progress = self.progress.clone();
+                generator.transforms = self.transforms.clone();
+                generator
+            })
+            .collect();
+
+        if self.config.output.format != OutputFormat::Json {
+            let roots: Vec<String> = generators.iter().map(|g| g.root_display_name()).collect();
+            let roots = roots.join(", ");
+            let file_count = || generators.iter().map(|g| g.content_paths().len()).sum();
+            self.write_header(output_file, &roots, file_count)?;
+            for (i, generator) in generators.iter().enumerate() {
+                if i > 0 {
+                    writeln!(output_file)?;
+                }
+                generator.write_report(output_file, output_names.clone(), None)?;
+                self.merge_root(generator);
+            }
+            return self.write_trailer(output_file, &roots, file_count);
+        }
+
+        let mut combined = JsonOutput {
+            root: JsonNode::directory("."),
+            total_tokens: None,
+            slowest: vec![],
+            summary: None,
+            licenses: BTreeMap::new(),
+            warnings: vec![],
+        };
+        for generator in &generators {
+            let mut json_output = generator.json_report(output_names.clone(), None);
+            let prefix = generator.root_display_name();
+            json_output.root.prefix_paths(&prefix);
+            for slow in &mut json_output.slowest {
+                slow.path = format!("{}/{}", prefix, slow.path);
+            }
+            if let JsonNode::Directory { children, .. } = &mut combined.root {
+                children.push(json_output.root);
+            }
+            if let Some(tokens) = json_output.total_tokens {
+                combined.total_tokens = Some(combined.total_tokens.unwrap_or(0) + tokens);
+            }
+            combined.slowest.extend(json_output.slowest);
+            if let Some(summary) = &json_output.summary {
+                combined
+                    .summary
+                    .get_or_insert_with(RunSummary::default)
+                    .merge(summary);
+            }
+            self.merge_root(generator);
+            for (license, files) in json_output.licenses {
+                combined
+                    .licenses
+                    .entry(license)
+                    .or_default()
+                    .extend(files.iter().map(|f| format!("{}/{}", prefix, f)));
+            }
+            combined.warnings.extend(json_output.warnings);
+        }
+        combined
+            .slowest
+            .sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+        combined.slowest.truncate(SLOWEST_FILES);
+        serde_json::to_writer_pretty(&mut *output_file, &combined)?;
+        writeln!(output_file)?;
+        output_file.flush()
+    }
+
+    fn split_report(
+        &self,
+        output_names: Vec<String>,
+        imported: Option<ImportedTree>,
+    ) -> Vec<Vec<u8>> {
+        let mut output_writer = self.output_writer(output_names);
+        let (tree, start_path) = self.load_tree(&mut output_writer, imported);
+        let file_list = self.content_files(&mut output_writer, &tree, start_path);
+
+        let mut header = vec![];
+        let _ = self.write_header(&mut header, &tree.root.name, || file_list.len());
+        self.write_tree(&output_writer, &tree, &mut header);
+        let chunks: Vec<Vec<u8>> = file_list
+            .iter()
+            .map(|file_path| {
+                let mut chunk = vec![];
+                output_writer.write_file_contents(
+                    std::slice::from_ref(file_path),
+                    &mut chunk,
+                    start_path,
+                );
+                chunk
+            })
+            .collect();
+        let mut footer = vec![];
+        self.write_footer(&output_writer, &mut footer);
+        let _ = self.write_trailer(&mut footer, &tree.root.name, || file_list.len());
+        self.record_summary(&output_writer);
+
+        let token_counter = self
+            .config
+            .output
+            .split_tokens
+            .map(|_| TokenCounter::new(self.config.output.tokenizer));
+        let measure = |bytes: &[u8]| {
+            let tokens = token_counter
+                .as_ref()
+                .map_or(0, |c| c.count(&String::from_utf8_lossy(bytes)));
+            (bytes.len() as u64, tokens)
+        };
+        let fits = |(bytes, tokens): (u64, usize)| {
+            self.config.output.split_size.is_none_or(|max| bytes <= max)
+                && self
+                    .config
+                    .output
+                    .split_tokens
+                    .is_none_or(|max| tokens <= max)
+        };
+        let add = |a: (u64, usize), b: (u64, usize)| (a.0 + b.0, a.1 + b.1);
+
+        let header_size = measure(&header);
+        let mut parts = vec![];
+        let mut part = header.clone();
+        let mut part_size = header_size;
+        let mut part_has_files = false;
+        for chunk in chunks {
+            let chunk_size = measure(&chunk);
+            if part_has_files && !fits(add(part_size, chunk_size)) {
+                parts.push(std::mem::replace(&mut part, header.clone()));
+                part_size = header_size;
+            }
+            part.extend_from_slice(&chunk);
+            part_size = add(part_size, chunk_size);
+            part_has_files = true;
+        }
+        if part_has_files && !fits(add(part_size, measure(&footer))) {
+            parts.push(std::mem::replace(&mut part, header));
+        }
+        part.extend_from_slice(&footer);
+        parts.push(part);
+        parts
+    }
+
+    fn load_tree<'p>(
+        &'p self,
+        output_writer: &mut OutputWriter,
+        imported: Option<ImportedTree>,
+    ) -> (ProjectTree, &'p Path) {
+        let (mut tree, start_path) = match imported {
+            Some(imported) => {
+                output_writer
+                    .warnings
+                    .borrow_mut()
+                    .extend(imported.tree.warnings.iter().cloned());
+                output_writer.imported = Some(imported.files);
+                (imported.tree, Path::new(""))
+            }
+            None => {
+                let start_path = Path::new(&self.config.general.path);
+                if self.config.output.anonymize == AnonymizeMode::Off {
+                    output_writer.read_cache = Some(ReadCache::new(self.config.io.max_buffer_size));
+                }
+                let tree = output_writer.build_tree(start_path, &self.root_display_name());
+                (tree, start_path)
+            }
+        };
+        if !self.config.filters.deep_patterns.is_empty() {
+            self.prune_overview(&mut tree.root, 0, start_path);
+        }
+        let mut directories = 0;
+        tree.root.walk(&mut |node| {
+            if node.kind == NodeKind::Directory {
+                directories += 1;
+            }
+        });
+        output_writer.summary.get_mut().directories = directories;
+        (tree, start_path)
+    }
+
+    fn prune_overview(&self, node: &mut TreeNode, level: u32, start_path: &Path) -> bool {
+        let deep_patterns = &self.config.filters.deep_patterns;
+        let mut has_deep = false;
+        node.children.retain_mut(|child| {
+            let deep = if child.is_dir() {
+                self.prune_overview(child, level + 1, start_path)
+            } else {
+                filters::matches_any(
+                    deep_patterns,
+                    &filters::relative_path(&child.path, start_path),
+                )
+            };
+            has_deep |= deep;
+            deep || level < self.config.general.overview_depth
+        });
+        has_deep
+    }
+
+    fn content_files(
+        &self,
+        output_writer: &mut OutputWriter,
+        tree: &ProjectTree,
+        start_path: &Path,
+    ) -> Vec<PathBuf> {
+        let files = tree.files();
+        let total = files.len();
+        let mut file_list = self.filter_file_list(files.clone(), Some(output_writer));
+        self.order_contents(tree, &mut file_list, start_path);
+        output_writer.sensitive = self.sensitive_files(&file_list);
+        let sensitive = &output_writer.sensitive;
+        output_writer.summary.get_mut().skipped_filtered +=
+            total - file_list.len() + sensitive.len();
+        if let Some(limit) = self.config.general.skip_content_over_dir_size {
+            let included: HashSet<&PathBuf> = file_list
+                .iter()
+                .filter(|f| !sensitive.contains(*f))
+                .collect();
+            let mut oversized_dirs = vec![];
+            Self::find_oversized_dirs(&tree.root, &included, limit, &mut oversized_dirs);
+            output_writer.oversized_dirs = oversized_dirs;
+            output_writer.summary.get_mut().skipped_too_big += file_list
+                .iter()
+                .filter(|f| output_writer.oversized_dir(f).is_some())
+                .count();
+        }
+        if self.config.general.count_excluded && self.config.general.from_json.is_none() {
+            let included: HashSet<&PathBuf> = file_list
+                .iter()
+                .filter(|f| !output_writer.sensitive.contains(*f))
+                .collect();
+            for file in files.iter().filter(|f| !included.contains(f)) {
+                output_writer.count_excluded(file);
+            }
+        }
+        if self.config.output.manifest.is_some() {
+            let included: HashSet<&PathBuf> = file_list.iter().collect();
+            let mut manifest = output_writer.manifest.borrow_mut();
+            tree.root.walk(&mut |node| {
+                let reason = match node.kind {
+                    NodeKind::File if !included.contains(&node.path) => "filtered",
+                    NodeKind::File if output_writer.oversized_dir(&node.path).is_some() => {
+                        "directory too big"
+                    }
+                    NodeKind::Binary => "binary",
+                    _ => return,
+                };
+                let rel_path = output_writer.display_path(&node.path, start_path);
+                manifest.push(ManifestEntry::skipped(&rel_path, node.size, reason));
+            });
+        }
+        let sampled: Vec<PathBuf> = file_list
+            .iter()
+            .filter(|f| !output_writer.sensitive.contains(*f))
+            .cloned()
+            .collect();
+        output_writer.unsampled =
+            sampling::unsampled_files(&sampled, start_path, &self.config.sampling);
+        let summarize_patterns = &self.config.filters.summarize_patterns;
+        output_writer.summarized = file_list
+            .iter()
+            .filter(|f| {
+                filters::matches_any(summarize_patterns, &filters::relative_path(f, start_path))
+            })
+            .cloned()
+            .collect();
+        if let Some(cache) = &output_writer.read_cache {
+            let included: HashSet<&Path> = file_list.iter().map(PathBuf::as_path).collect();
+            cache.retain(|f| {
+                included.contains(f)
+                    && !output_writer.sensitive.contains(f)
+                    && !output_writer.unsampled.contains(f)
+                    && output_writer.oversized_dir(f).is_none()
+            });
+        }
+        file_list
+    }
+
+    fn find_oversized_dirs(
+        node: &TreeNode,
+        included: &HashSet<&PathBuf>,
+        limit: u64,
+        found: &mut Vec<(PathBuf, u64)>,
+    ) -> u64 {
+        let mut size = 0;
+        for child in &node.children {
+            if child.is_dir() {
+                size += Self::find_oversized_dirs(child, included, limit, found);
+            } else if included.contains(&child.path) {
+                size += child.size;
+            }
+        }
+        if node.is_dir() && size > limit {
+            found.push((node.path.clone(), size));
+            return 0;
+        }
+        size
+    }
+
+    fn record_summary(&self, output_writer: &OutputWriter) {
+        self.summary
+            .borrow_mut()
+            .merge(&output_writer.summary.borrow());
+        self.manifest
+            .borrow_mut()
+            .append(&mut output_writer.manifest.borrow_mut());
+    }
+
+    fn merge_root(&self, generator: &ProjectTreeGenerator) {
+        self.summary.borrow_mut().merge(&generator.summary());
+        let prefix = generator.root_display_name();
+        self.manifest
+            .borrow_mut()
+            .extend(generator.manifest.take().into_iter().map(|mut entry| {
+                entry.path = format!("{}/{}", prefix, entry.path);
+                entry
+            }));
+    }
+
+    fn write_tree(
+        &self,
+        output_writer: &OutputWriter,
+        tree: &ProjectTree,
+        output_file: &mut dyn Write,
+    ) {
+        match self.config.output.format {
+            OutputFormat::Markdown => {
+                let _ = writeln!(output_file, "```text");
+                output_writer.render_tree(&tree.root, output_file);
+                let _ = writeln!(output_file, "```");
+            }
+            OutputFormat::XmlDocs => {
+                let mut rendered = vec![];
+                output_writer.render_tree(&tree.root, &mut rendered);
+                let _ = write!(
+                    output_file,
+                    "<tree>\n{}</tree>\n",
+                    FileUtils::xml_escape(&String::from_utf8_lossy(&rendered))
+                );
+            }
+            _ => output_writer.render_tree(&tree.root, output_file),
+        }
+    }
+
+    fn write_header(
+        &self,
+        output_file: &mut dyn Write,
+        root: &str,
+        file_count: impl FnOnce() -> usize,
+    ) -> io::Result<()> {
+        match self.render_template(self.config.output.header.as_deref(), root, file_count) {
+            Some(header) => writeln!(output_file, "{}\n", header),
+            None => Ok(()),
+        }
+    }
+
+    fn write_trailer(
+        &self,
+        output_file: &mut dyn Write,
+        root: &str,
+        file_count: impl FnOnce() -> usize,
+    ) -> io::Result<()> {
+        match self.render_template(self.config.output.footer.as_deref(), root, file_count) {
+            Some(footer) => writeln!(output_file, "\n{}", footer),
+            None => Ok(()),
+        }
+    }
+
+    fn render_template(
+        &self,
+        template: Option<&str>,
+        root: &str,
+        file_count: impl FnOnce() -> usize,
+    ) -> Option<String> {
+        let mut text = template?
+            .trim_end_matches('\n')
+            .replace("{root}", root)
+            .replace("{date}", &naming::today_utc());
+        if text.contains("{file_count}") {
+            text = text.replace("{file_count}", &file_count().to_string());
+        }
+        Some(text)
+    }
+
+    fn write_footer(&self, output_writer: &OutputWriter, output_file: &mut dyn Write) {
+        output_writer.write_token_summary(output_file);
+        output_writer.write_license_summary(output_file);
+        let warnings = output_writer.warnings.borrow();
+        if !warnings.is_empty() {
+            let _ = writeln!(output_file, "\nWarnings:");
+            for warning in warnings.iter() {
+                let _ = writeln!(output_file, "- {}", warning);
+            }
+        }
+        if self.config.output.summary == SummaryMode::Append {
+            let _ = write!(output_file, "\n{}", output_writer.summary.borrow());
+        }
+    }
+
+    fn root_display_name(&self) -> String {
+        let start_path = Path::new(&self.config.general.path);
+        start_path
+            .canonicalize()
+            .unwrap_or(start_path.to_path_buf())
+            .file_name()
+            .unwrap_or_else(|| std::ffi::OsStr::new("."))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn file_marker(
+        &self,
+        file_path: &Path,
+        output_writer: Option<&OutputWriter>,
+    ) -> Option<FileMarker> {
+        if self.config.general.from_json.is_some() {
+            return None;
+        }
+        match output_writer {
+            Some(output_writer) => output_writer.file_marker(file_path),
+            None => FileUtils::file_marker(file_path, &self.throttle),
+        }
+    }
+
+    fn filter_file_list(
+        &self,
+        file_list: Vec<PathBuf>,
+        output_writer: Option<&OutputWriter>,
+    ) -> Vec<PathBuf> {
+        let start_path = match self.config.general.from_json {
+            Some(_) => Path::new(""),
+            None => Path::new(&self.config.general.path),
+        };
+        let include_patterns = &self.config.filters.include_patterns;
         let exclude_patterns = &self.config.filters.exclude_patterns;
+        let mut forced = HashSet::new();
+        let mut file_list: Vec<PathBuf> = file_list
+            .into_iter()
+            .filter(|f| match self.file_marker(f, output_writer) {
+                Some(FileMarker::IgnoreFile) => false,
+                Some(FileMarker::Include) => {
+                    forced.insert(f.clone());
+                    true
+                }
+                None => true,
+            })
+            .collect();
         if !include_patterns.is_empty() {
             file_list.retain(|f| {
-                let name = f.file_name().unwrap_or_default().to_string_lossy();
-                include_patterns.iter().any(|p| {
-                    glob::Pattern::new(p)
-                        .ok()
-                        .map_or(false, |gp| gp.matches(&name))
-                })
+                if forced.contains(f) {
+                    return true;
+                }
+                filters::matches_any(include_patterns, &filters::relative_path(f, start_path))
+            });
+        }
+        if let Some(Ok(changed)) = &self.changed_files {
+            file_list.retain(|f| forced.contains(f) || changed.contains(f, false));
+        }
+        let deep_patterns = &self.config.filters.deep_patterns;
+        if !deep_patterns.is_empty() {
+            file_list.retain(|f| {
+                forced.contains(f)
+                    || filters::matches_any(deep_patterns, &filters::relative_path(f, start_path))
             });
         }
         if !exclude_patterns.is_empty() {
             file_list.retain(|f| {
-                let name = f.file_name().unwrap_or_default().to_string_lossy();
-                !exclude_patterns.iter().any(|p| {
-                    glob::Pattern::new(p)
-                        .ok()
-                        .map_or(false, |gp| gp.matches(&name))
-                })
+                if forced.contains(f) {
+                    return true;
+                }
+                !filters::matches_any(exclude_patterns, &filters::relative_path(f, start_path))
             });
         }
+        if let Some(permission_filter) = &self.permission_filter {
+            if self.config.general.from_json.is_none() {
+                file_list.retain(|f| forced.contains(f) || !permission_filter.excludes(f));
+            }
+        }
         file_list
     }
+
+    fn sensitive_files(&self, file_list: &[PathBuf]) -> HashSet<PathBuf> {
+        if !self.config.filters.default_excludes {
+            return HashSet::new();
+        }
+        let start_path = match self.config.general.from_json {
+            Some(_) => Path::new(""),
+            None => Path::new(&self.config.general.path),
+        };
+        file_list
+            .iter()
+            .filter(|f| {
+                let rel_path = filters::relative_path(f, start_path);
+                filters::DEFAULT_EXCLUDE_PATTERNS
+                    .iter()
+                    .any(|p| filters::pattern_matches(p, &rel_path))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+fn visible_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut rest = line;
+    while let Some(start) = rest.find('\x1b') {
+        width += rest[..start].width();
+        rest = rest[start..]
+            .find('m')
+            .map_or("", |end| &rest[start + end + 1..]);
+    }
+    width + rest.width()
 }
@@ -0,0 +1,264 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+pub struct Repository {
+    pub work_tree: PathBuf,
+    pub git_dir: PathBuf,
+}
+
+impl Repository {
+    pub fn discover(path: &Path) -> Option<Self> {
+        let absolute = std::path::absolute(path).ok()?;
+        for dir in absolute.ancestors() {
+            let dot_git = dir.join(".git");
+            if dot_git.is_dir() {
+                return Some(Repository {
+                    work_tree: dir.to_path_buf(),
+                    git_dir: dot_git,
+                });
+            }
+            if dot_git.is_file() {
+                let contents = fs::read_to_string(&dot_git).ok()?;
+                let gitdir = contents.strip_prefix("gitdir:")?.trim();
+                return Some(Repository {
+                    work_tree: dir.to_path_buf(),
+                    git_dir: dir.join(gitdir),
+                });
+            }
+        }
+        None
+    }
+
+    pub fn common_dir(&self) -> PathBuf {
+        match fs::read_to_string(self.git_dir.join("commondir")) {
+            Ok(contents) => self.git_dir.join(contents.trim()),
+            Err(_) => self.git_dir.clone(),
+        }
+    }
+
+    pub fn info_exclude(&self) -> PathBuf {
+        self.common_dir().join("info").join("exclude")
+    }
+
+    pub fn excludes_file(&self) -> Option<PathBuf> {
+        let home = home_dir();
+        let xdg_config = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home.as_ref().map(|h| h.join(".config")));
+        let config_files = [
+            Some(self.common_dir().join("config")),
+            home.as_ref().map(|h| h.join(".gitconfig")),
+            xdg_config.as_ref().map(|x| x.join("git").join("config")),
+        ];
+        let configured = config_files
+            .iter()
+            .flatten()
+            .find_map(|f| config_value(f, "core", "excludesfile"));
+        match configured {
+            Some(value) => Some(expand_home(&value, home.as_deref())),
+            None => xdg_config.map(|x| x.join("git").join("ignore")),
+        }
+    }
+}
+
+pub struct GitPathSet {
+    files: HashSet<PathBuf>,
+    dirs: HashSet<PathBuf>,
+    submodules: HashMap<PathBuf, String>,
+    symlinks: HashMap<PathBuf, PathBuf>,
+}
+
+impl GitPathSet {
+    pub fn tracked(start_path: &Path) -> Result<Self, String> {
+        let mut set = Self::empty();
+        let output = run_git(start_path, &["ls-files", "-z", "--stage"])?;
+        let checks_out_links = run_git(start_path, &["config", "--bool", "core.symlinks"])
+            .map_or(true, |value| value.trim_ascii() != b"false");
+        for entry in output.split(|&b| b == 0).filter(|e| !e.is_empty()) {
+            let entry = String::from_utf8_lossy(entry);
+            let Some((info, rel_path)) = entry.split_once('\t') else {
+                continue;
+            };
+            let path = set.insert(start_path, rel_path);
+            let mut fields = info.split(' ');
+            match (fields.next(), fields.next()) {
+                (Some("160000"), Some(commit)) => {
+                    set.submodules.insert(path, commit.to_string());
+                }
+                (Some("120000"), _) if !checks_out_links => {
+                    if let Ok(target) = fs::read_to_string(&path) {
+                        set.symlinks.insert(path, PathBuf::from(target));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(set)
+    }
+
+    pub fn changed_since(start_path: &Path, git_ref: &str) -> Result<Self, String> {
+        let mut set = Self::empty();
+        set.add_output(
+            start_path,
+            &[
+                "diff",
+                "--name-only",
+                "-z",
+                "--relative",
+                "--diff-filter=d",
+                git_ref,
+                "--",
+            ],
+        )?;
+        set.add_output(
+            start_path,
+            &["ls-files", "-z", "--others", "--exclude-standard"],
+        )?;
+        Ok(set)
+    }
+
+    fn empty() -> Self {
+        GitPathSet {
+            files: HashSet::new(),
+            dirs: HashSet::new(),
+            submodules: HashMap::new(),
+            symlinks: HashMap::new(),
+        }
+    }
+
+    fn add_output(&mut self, start_path: &Path, args: &[&str]) -> Result<(), String> {
+        let output = run_git(start_path, args)?;
+        for entry in output.split(|&b| b == 0).filter(|e| !e.is_empty()) {
+            self.insert(start_path, &String::from_utf8_lossy(entry));
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, start_path: &Path, rel_path: &str) -> PathBuf {
+        let path = start_path.join(rel_path);
+        for dir in path.ancestors().skip(1) {
+            if dir == start_path || !self.dirs.insert(dir.to_path_buf()) {
+                break;
+            }
+        }
+        self.files.insert(path.clone());
+        path
+    }
+
+    pub fn contains(&self, path: &Path, is_dir: bool) -> bool {
+        self.files.contains(path) || (is_dir && self.dirs.contains(path))
+    }
+
+    pub fn submodule(&self, path: &Path) -> Option<&str> {
+        self.submodules.get(path).map(String::as_str)
+    }
+
+    pub fn symlink_target(&self, path: &Path) -> Option<&Path> {
+        self.symlinks.get(path).map(PathBuf::as_path)
+    }
+}
+
+fn run_git(start_path: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = git(start_path)?
+        .args(args)
+        .output()
+        .map_err(|e| format!("cannot run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(output.stdout)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub author: String,
+    pub date: String,
+}
+
+impl CommitInfo {
+    pub fn last_for(path: &Path) -> Option<Self> {
+        let dir = path.parent().filter(|d| !d.as_os_str().is_empty())?;
+        let output = git(dir)
+            .ok()?
+            .args(["log", "-1", "--format=%h%x00%an%x00%as", "--"])
+            .arg(path.file_name()?)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut fields = stdout.trim_end().splitn(3, '\0');
+        let hash = fields.next().filter(|h| !h.is_empty())?.to_string();
+        Some(CommitInfo {
+            hash,
+            author: fields.next()?.to_string(),
+            date: fields.next()?.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for CommitInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{} by {}, {}]", self.hash, self.author, self.date)
+    }
+}
+
+#[cfg(feature = "git")]
+fn git(dir: &Path) -> Result<Command, String> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir);
+    Ok(command)
+}
+
+#[cfg(not(feature = "git"))]
+fn git(_dir: &Path) -> Result<Command, String> {
+    Err("projct was built without the git feature".to_string())
+}
+
+pub fn find_git_dir(path: &Path) -> Option<PathBuf> {
+    Repository::discover(path).map(|repo| repo.git_dir)
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+fn expand_home(value: &str, home: Option<&Path>) -> PathBuf {
+    match (value.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(value),
+    }
+}
+
+fn config_value(config_path: &Path, section: &str, key: &str) -> Option<String> {
+    let contents = fs::read_to_string(config_path).ok()?;
+    let mut in_section = false;
+    let mut value = None;
+    for line in contents.lines() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header.trim_end_matches(']').trim();
+            in_section = name.eq_ignore_ascii_case(section);
+            continue;
+        }
+        if !in_section || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some((k, v)) = line.split_once('=') {
+            if k.trim().eq_ignore_ascii_case(key) {
+                value = Some(v.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    value
+}
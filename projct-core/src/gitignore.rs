@@ -1,53 +1,233 @@
-use glob;
 use regex::Regex;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufRead};
-use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::path::{Component, Path, PathBuf};
 
-#[derive(Clone)]
+use crate::git::Repository;
+
+const BUILTIN_PATTERNS: &[&str] = &[
+    ".git",
+    ".gitattributes",
+    ".gitignore",
+    ".projct",
+    ".projctignore",
+];
+const IGNORE_FILE_NAMES: &[&str] = &[".gitignore", ".projctignore"];
+
+pub fn is_git_dir(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == ".git")
+}
+
+pub fn is_builtin(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| BUILTIN_PATTERNS.iter().any(|pattern| name == *pattern))
+}
+
+#[derive(Clone, Debug)]
 pub struct GitPattern {
-    pattern: String,
+    pub source: String,
+    pub line: usize,
+    regex: Option<Regex>,
     is_negative: bool,
     is_directory_only: bool,
-    is_absolute: bool,
+    is_anchored: bool,
+}
+
+impl GitPattern {
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = trim_trailing_spaces(line.trim_end_matches(['\r', '\n']));
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (is_negative, body) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (is_directory_only, body) = match body.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, body),
+        };
+        if body.is_empty() {
+            return None;
+        }
+        let is_anchored = body.contains('/');
+        let body = body.strip_prefix('/').unwrap_or(body);
+        let regex = glob_to_regex(body).and_then(|re| Regex::new(&format!("^{}$", re)).ok());
+        Some(GitPattern {
+            source: line.to_string(),
+            line: 0,
+            regex,
+            is_negative,
+            is_directory_only,
+            is_anchored,
+        })
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.is_negative
+    }
+
+    pub fn matches(&self, rel_path: &str, is_directory: bool) -> bool {
+        let Some(regex) = &self.regex else {
+            return false;
+        };
+        if self.is_directory_only && !is_directory {
+            return false;
+        }
+        if self.is_anchored {
+            regex.is_match(rel_path)
+        } else {
+            let name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+            regex.is_match(name)
+        }
+    }
+}
+
+fn trim_trailing_spaces(line: &str) -> &str {
+    let mut end = line.len();
+    while line[..end].ends_with(' ') {
+        let escapes = line[..end - 1]
+            .bytes()
+            .rev()
+            .take_while(|&b| b == b'\\')
+            .count();
+        if escapes % 2 == 1 {
+            break;
+        }
+        end -= 1;
+    }
+    &line[..end]
 }
 
-#[derive(Clone)]
+fn glob_to_regex(glob: &str) -> Option<String> {
+    let chars: Vec<char> = glob.chars().collect();
+    let len = chars.len();
+    let mut re = String::new();
+    let mut i = 0;
+    while i < len {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                let at_segment_start = i == 0 || chars[i - 1] == '/';
+                let at_segment_end = i + 2 == len || chars[i + 2] == '/';
+                if at_segment_start && at_segment_end {
+                    if i + 2 == len {
+                        re.push_str(".*");
+                        i += 2;
+                    } else {
+                        re.push_str("(?:.*/)?");
+                        i += 3;
+                    }
+                } else {
+                    re.push_str("[^/]*");
+                    while chars.get(i) == Some(&'*') {
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            '[' => {
+                let (class, next) = parse_class(&chars, i)?;
+                re.push_str(&class);
+                i = next;
+                continue;
+            }
+            '\\' => {
+                re.push_str(&regex::escape(&chars.get(i + 1)?.to_string()));
+                i += 2;
+                continue;
+            }
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    Some(re)
+}
+
+fn parse_class(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut i = start + 1;
+    let negated = matches!(chars.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+    let mut items = String::new();
+    let mut first = true;
+    loop {
+        let c = *chars.get(i)?;
+        if c == ']' && !first {
+            break;
+        }
+        first = false;
+        if c == '[' && chars.get(i + 1) == Some(&':') {
+            let name_end = (i + 2..chars.len().saturating_sub(1))
+                .find(|&j| chars[j] == ':' && chars[j + 1] == ']');
+            if let Some(end) = name_end {
+                let name: String = chars[i + 2..end].iter().collect();
+                items.push_str(&format!("[:{}:]", name));
+                i = end + 2;
+                continue;
+            }
+        }
+        let (low, width) = class_char(chars, i)?;
+        if chars.get(i + width) == Some(&'-') && chars.get(i + width + 1).is_some_and(|&c| c != ']')
+        {
+            let (high, high_width) = class_char(chars, i + width + 1)?;
+            items.push_str(&format!("{}-{}", class_escape(low), class_escape(high)));
+            i += width + 1 + high_width;
+            continue;
+        }
+        items.push_str(&class_escape(low));
+        i += width;
+    }
+    let class = if negated {
+        format!("[^/{}]", items)
+    } else {
+        format!("[{}&&[^/]]", items)
+    };
+    Some((class, i + 1))
+}
+
+fn class_char(chars: &[char], i: usize) -> Option<(char, usize)> {
+    match chars.get(i)? {
+        '\\' => chars.get(i + 1).map(|&c| (c, 2)),
+        &c => Some((c, 1)),
+    }
+}
+
+fn class_escape(c: char) -> String {
+    format!("\\x{{{:x}}}", c as u32)
+}
+
+pub(crate) fn relative_slash_path(path: &Path, base: &Path) -> Option<String> {
+    let rel = path.strip_prefix(base).ok()?;
+    let mut parts = vec![];
+    for component in rel.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+#[derive(Clone, Debug)]
 pub struct GitignoreParser {
     patterns: Vec<GitPattern>,
-    gitignore_dir: String,
+    gitignore_dir: PathBuf,
 }
 
 impl GitignoreParser {
     pub fn new(gitignore_path: Option<&Path>) -> Self {
-        let mut patterns = vec![];
-        patterns.push(GitPattern {
-            pattern: ".git".to_string(),
-            is_negative: false,
-            is_directory_only: true,
-            is_absolute: false,
-        });
-        patterns.push(GitPattern {
-            pattern: ".gitattributes".to_string(),
-            is_negative: false,
-            is_directory_only: false,
-            is_absolute: false,
-        });
-        patterns.push(GitPattern {
-            pattern: ".gitignore".to_string(),
-            is_negative: false,
-            is_directory_only: false,
-            is_absolute: false,
-        });
-
         let gitignore_dir = gitignore_path
             .and_then(|p| p.parent())
-            .map(|p| p.to_string_lossy().to_string())
+            .map(Path::to_path_buf)
             .unwrap_or_default();
         let mut parser = GitignoreParser {
-            patterns,
+            patterns: vec![],
             gitignore_dir,
         };
         if let Some(path) = gitignore_path {
@@ -58,191 +238,195 @@ impl GitignoreParser {
         parser
     }
 
+    pub fn from_lines<'l>(base_dir: &Path, lines: impl IntoIterator<Item = &'l str>) -> Self {
+        GitignoreParser {
+            patterns: lines
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, line)| {
+                    GitPattern::parse(line).map(|p| GitPattern { line: i + 1, ..p })
+                })
+                .collect(),
+            gitignore_dir: base_dir.to_path_buf(),
+        }
+    }
+
     fn load_patterns(&mut self, gitignore_path: &Path) {
         let file = match File::open(gitignore_path) {
             Ok(f) => f,
             Err(e) => {
-                println!("[Warning: Cannot read {}: {}]", gitignore_path.display(), e);
+                eprintln!("[Warning: Cannot read {}: {}]", gitignore_path.display(), e);
                 return;
             }
         };
         let reader = io::BufReader::new(file);
-        for line in reader.lines() {
+        for (i, line) in reader.lines().enumerate() {
             let line = match line {
-                Ok(l) => l.trim().to_string(),
+                Ok(l) => l,
                 Err(_) => continue,
             };
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-            if let Some(pattern) = self.parse_pattern(&line) {
-                self.patterns.push(pattern);
+            if let Some(pattern) = GitPattern::parse(&line) {
+                self.patterns.push(GitPattern {
+                    line: i + 1,
+                    ..pattern
+                });
             }
         }
     }
 
-    fn parse_pattern(&self, pattern_line: &str) -> Option<GitPattern> {
-        let mut pattern_line = pattern_line.replace("\\ ", " ");
-        let is_negative = pattern_line.starts_with('!');
-        if is_negative {
-            pattern_line = pattern_line[1..].to_string();
-        }
-        let is_directory_only = pattern_line.ends_with('/');
-        if is_directory_only {
-            pattern_line = pattern_line[..pattern_line.len() - 1].to_string();
-        }
-        let is_absolute = pattern_line.starts_with('/');
-        if is_absolute {
-            pattern_line = pattern_line[1..].to_string();
-        }
-        let re = Regex::new(r"([?\[\]])").unwrap();
-        let pattern_line = re.replace_all(&pattern_line, r"[$1]").to_string();
-        Some(GitPattern {
-            pattern: pattern_line,
-            is_negative,
-            is_directory_only,
-            is_absolute,
-        })
+    pub fn patterns(&self) -> &[GitPattern] {
+        &self.patterns
     }
 
-    pub fn should_ignore(&self, path: &Path, is_directory: bool, parent_ignored: bool) -> bool {
-        if self.patterns.is_empty() {
-            return parent_ignored;
-        }
-        let gitignore_dir = Path::new(&self.gitignore_dir);
-        let rel_path = match path.strip_prefix(gitignore_dir) {
-            Ok(r) => r.to_path_buf(),
-            Err(_) => return parent_ignored,
-        };
-        if rel_path.starts_with("..") {
-            return parent_ignored;
-        }
-        let mut match_path = rel_path.to_string_lossy().to_string();
-        if is_directory {
-            match_path.push('/');
-        }
-        let mut result = parent_ignored;
-        let mut last_negative_match = false;
-        for pattern_info in &self.patterns {
-            if pattern_info.is_directory_only && !is_directory {
-                continue;
-            }
-            let target_path = if pattern_info.is_absolute {
-                &match_path
-            } else {
-                &match_path
-            };
-            if self.matches_pattern(target_path, &pattern_info.pattern, pattern_info.is_absolute) {
-                if pattern_info.is_negative {
-                    last_negative_match = true;
-                    result = false;
-                } else {
-                    result = true;
-                    last_negative_match = false;
-                }
-            }
-        }
-        result && !last_negative_match
+    pub fn gitignore_dir(&self) -> &Path {
+        &self.gitignore_dir
     }
 
-    fn matches_pattern(&self, path: &str, pattern: &str, is_absolute: bool) -> bool {
-        if pattern == "**" {
-            return true;
-        }
-        let pattern = pattern.replace("**", "*");
-        let glob_pattern = match glob::Pattern::new(&pattern) {
-            Ok(p) => p,
-            Err(_) => return false,
-        };
-        if is_absolute {
-            if glob_pattern.matches(path) {
-                return true;
-            }
-            let alt_pattern = format!("*/{}", pattern);
-            let alt_glob = match glob::Pattern::new(&alt_pattern) {
-                Ok(p) => p,
-                Err(_) => return false,
-            };
-            alt_glob.matches(path)
-        } else {
-            path.split('/').any(|segment| glob_pattern.matches(segment))
+    pub fn matches(&self, path: &Path, is_directory: bool) -> Option<bool> {
+        let rel_path = relative_slash_path(path, &self.gitignore_dir)?;
+        if rel_path.is_empty() {
+            return None;
         }
+        self.patterns
+            .iter()
+            .rev()
+            .find(|p| p.matches(&rel_path, is_directory))
+            .map(|p| !p.is_negative)
     }
 }
 
 pub struct HierarchicalGitignoreManager {
     start_path: PathBuf,
-    parsers_by_dir: HashMap<PathBuf, Vec<GitignoreParser>>,
+    absolute_start: PathBuf,
+    builtin: GitignoreParser,
+    repo_excludes: Vec<GitignoreParser>,
+    ancestor_parsers: Vec<GitignoreParser>,
+    ignore_file_names: Vec<String>,
+    parsers_by_dir: RefCell<HashMap<PathBuf, Vec<GitignoreParser>>>,
+    ignored_dirs: RefCell<HashMap<PathBuf, bool>>,
 }
 
 impl HierarchicalGitignoreManager {
-    pub fn new(start_path: &Path) -> Self {
+    pub fn new(start_path: &Path, extra_ignore_files: &[String]) -> Self {
         let mut manager = HierarchicalGitignoreManager {
             start_path: start_path.to_path_buf(),
-            parsers_by_dir: HashMap::new(),
+            absolute_start: std::path::absolute(start_path)
+                .unwrap_or_else(|_| start_path.to_path_buf()),
+            builtin: GitignoreParser::from_lines(start_path, BUILTIN_PATTERNS.iter().copied()),
+            repo_excludes: vec![],
+            ancestor_parsers: vec![],
+            ignore_file_names: IGNORE_FILE_NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .chain(extra_ignore_files.iter().cloned())
+                .collect(),
+            parsers_by_dir: RefCell::new(HashMap::new()),
+            ignored_dirs: RefCell::new(HashMap::new()),
         };
-        manager.load_all_gitignores();
+        manager.load_repo_ignores();
         manager
     }
 
-    fn load_all_gitignores(&mut self) {
-        for entry in WalkDir::new(&self.start_path) {
-            let entry = match entry {
-                Ok(e) => e,
-                Err(e) => {
-                    println!("[Warning: {}]", e);
-                    continue;
-                }
+    fn load_repo_ignores(&mut self) {
+        let Some(repo) = Repository::discover(&self.start_path) else {
+            return;
+        };
+        let exclude_files = [repo.excludes_file(), Some(repo.info_exclude())];
+        for exclude_file in exclude_files.iter().flatten() {
+            let Ok(contents) = std::fs::read_to_string(exclude_file) else {
+                continue;
             };
-            if entry.file_name() == ".gitignore" {
-                let parser = GitignoreParser::new(Some(entry.path()));
-                let dir = entry.path().parent().unwrap().to_path_buf();
-                self.parsers_by_dir.entry(dir).or_default().push(parser);
+            self.repo_excludes.push(GitignoreParser::from_lines(
+                &repo.work_tree,
+                contents.lines(),
+            ));
+        }
+        let ancestors: Vec<PathBuf> = self
+            .absolute_start
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&repo.work_tree))
+            .map(Path::to_path_buf)
+            .collect();
+        for dir in ancestors.iter().rev() {
+            for name in &self.ignore_file_names {
+                let path = dir.join(name);
+                if path.is_file() {
+                    self.ancestor_parsers
+                        .push(GitignoreParser::new(Some(&path)));
+                }
             }
         }
     }
 
-    fn find_relevant_parsers(&self, path: &Path) -> Vec<GitignoreParser> {
-        let mut relevant = vec![];
-        let mut current = if path.is_dir() {
-            path.to_path_buf()
-        } else {
-            path.parent().unwrap().to_path_buf()
-        };
-        loop {
-            if let Some(parsers) = self.parsers_by_dir.get(&current) {
-                relevant.extend_from_slice(parsers);
-            }
-            if let Some(parent) = current.parent() {
-                if parent == current {
-                    break;
-                }
-                current = parent.to_path_buf();
-            } else {
-                break;
-            }
+    fn ensure_loaded(&self, dir: &Path) {
+        if self.parsers_by_dir.borrow().contains_key(dir) {
+            return;
         }
-        relevant
+        let parsers = self
+            .ignore_file_names
+            .iter()
+            .map(|name| dir.join(name))
+            .filter(|path| path.is_file())
+            .map(|path| GitignoreParser::new(Some(&path)))
+            .collect();
+        self.parsers_by_dir
+            .borrow_mut()
+            .insert(dir.to_path_buf(), parsers);
     }
 
     pub fn should_ignore(&self, path: &Path, is_directory: bool) -> bool {
-        let mut relevant_parsers = self.find_relevant_parsers(path);
-        if relevant_parsers.is_empty() {
+        if let Some(parent) = path.parent() {
+            if self.is_dir_ignored(parent) {
+                return true;
+            }
+        }
+        self.matches_path(path, is_directory)
+    }
+
+    fn is_dir_ignored(&self, dir: &Path) -> bool {
+        if dir == self.start_path || !dir.starts_with(&self.start_path) {
             return false;
         }
-        relevant_parsers.sort_by_key(|p| p.gitignore_dir.len());
-        let mut ignored = false;
-        let mut last_negative_override = false;
-        for parser in relevant_parsers {
-            let current_ignored = parser.should_ignore(path, is_directory, ignored);
-            if ignored && !current_ignored {
-                last_negative_override = true;
-                ignored = false;
-            } else {
-                ignored = current_ignored;
-                last_negative_override = false;
+        if let Some(&ignored) = self.ignored_dirs.borrow().get(dir) {
+            return ignored;
+        }
+        let ignored = self.should_ignore(dir, true);
+        self.ignored_dirs
+            .borrow_mut()
+            .insert(dir.to_path_buf(), ignored);
+        ignored
+    }
+
+    fn matches_path(&self, path: &Path, is_directory: bool) -> bool {
+        let mut result = self.builtin.matches(path, is_directory);
+        if !self.repo_excludes.is_empty() || !self.ancestor_parsers.is_empty() {
+            let absolute = match path.strip_prefix(&self.start_path) {
+                Ok(rel) => self.absolute_start.join(rel),
+                Err(_) => path.to_path_buf(),
+            };
+            for parser in self.repo_excludes.iter().chain(&self.ancestor_parsers) {
+                if let Some(ignored) = parser.matches(&absolute, is_directory) {
+                    result = Some(ignored);
+                }
+            }
+        }
+        let dirs: Vec<&Path> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|d| d.starts_with(&self.start_path))
+            .collect();
+        for dir in &dirs {
+            self.ensure_loaded(dir);
+        }
+        let parsers_by_dir = self.parsers_by_dir.borrow();
+        for dir in dirs.iter().rev() {
+            for parser in parsers_by_dir.get(*dir).into_iter().flatten() {
+                if let Some(ignored) = parser.matches(path, is_directory) {
+                    result = Some(ignored);
+                }
             }
         }
-        ignored && !last_negative_override
+        result == Some(true)
     }
 }
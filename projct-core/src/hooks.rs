@@ -0,0 +1,54 @@
+use std::io;
+use std::process::Command;
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+
+pub fn run(config: &Config, stage: &'static str, output: &str) -> Result<()> {
+    let command = match stage {
+        "pre" => &config.hooks.pre,
+        _ => &config.hooks.post,
+    };
+    let Some(command) = command else {
+        return Ok(());
+    };
+    if !config.hooks.run {
+        eprintln!(
+            "[Skipped {} hook (pass --run-hooks to run it): {}]",
+            stage, command
+        );
+        return Ok(());
+    }
+    eprintln!("Running {} hook: {}", stage, command);
+    let status = shell(command)
+        .env("PROJCT_HOOK", stage)
+        .env("PROJCT_OUTPUT", output)
+        .env("PROJCT_ROOT", &config.general.path)
+        .stdout(io::stderr())
+        .status()
+        .map_err(|e| Error::Hook {
+            stage,
+            message: format!("cannot start `{}`: {}", command, e),
+        })?;
+    if !status.success() {
+        return Err(Error::Hook {
+            stage,
+            message: format!("`{}` exited with {}", command, status),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::filters;
+use crate::git::CommitInfo;
+use crate::summary::RunSummary;
+use crate::tree::{NodeKind, ProjectTree, TreeNode};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum JsonNode {
+    Directory {
+        name: String,
+        children: Vec<JsonNode>,
+    },
+    File {
+        name: String,
+        path: String,
+        size: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tokens: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timings: Option<FileTimings>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        commit: Option<CommitInfo>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct FileTimings {
+    pub read_ms: f64,
+    pub transform_ms: f64,
+    pub write_ms: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SlowFile {
+    pub path: String,
+    pub total_ms: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonOutput {
+    pub root: JsonNode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_tokens: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub slowest: Vec<SlowFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<RunSummary>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub licenses: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+pub struct ImportedFile {
+    pub size: u64,
+    pub content: std::result::Result<String, String>,
+}
+
+pub struct ImportedTree {
+    pub tree: ProjectTree,
+    pub files: HashMap<PathBuf, ImportedFile>,
+}
+
+pub fn import(path: &Path) -> Result<ImportedTree> {
+    let import_error = |message: String| Error::Import {
+        path: path.to_path_buf(),
+        message,
+    };
+    let contents = fs::read_to_string(path).map_err(|e| import_error(e.to_string()))?;
+    let output: JsonOutput =
+        serde_json::from_str(&contents).map_err(|e| import_error(e.to_string()))?;
+    let mut files = HashMap::new();
+    let root = output.root.into_tree_node(None, &mut files);
+    Ok(ImportedTree {
+        tree: ProjectTree {
+            root,
+            warnings: output.warnings,
+        },
+        files,
+    })
+}
+
+impl FileTimings {
+    pub fn new(read: Duration, transform: Duration, write: Duration) -> Self {
+        FileTimings {
+            read_ms: millis(read),
+            transform_ms: millis(transform),
+            write_ms: millis(write),
+        }
+    }
+
+    pub fn total_ms(&self) -> f64 {
+        millis_rounded(self.read_ms + self.transform_ms + self.write_ms)
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    millis_rounded(duration.as_secs_f64() * 1000.0)
+}
+
+fn millis_rounded(ms: f64) -> f64 {
+    (ms * 1000.0).round() / 1000.0
+}
+
+impl JsonNode {
+    pub fn directory(name: &str) -> Self {
+        JsonNode::Directory {
+            name: name.to_string(),
+            children: vec![],
+        }
+    }
+
+    pub fn from_tree(node: &TreeNode) -> Self {
+        if node.is_dir() {
+            JsonNode::Directory {
+                name: node.name.clone(),
+                children: node.children.iter().map(JsonNode::from_tree).collect(),
+            }
+        } else {
+            JsonNode::File {
+                name: node.name.clone(),
+                path: filters::slash_path(&node.path),
+                size: node.size,
+                content: None,
+                note: None,
+                tokens: None,
+                timings: None,
+                commit: None,
+            }
+        }
+    }
+
+    fn into_tree_node(
+        self,
+        parent: Option<&Path>,
+        files: &mut HashMap<PathBuf, ImportedFile>,
+    ) -> TreeNode {
+        match self {
+            JsonNode::Directory { name, children } => {
+                let path = parent.map_or_else(PathBuf::new, |p| p.join(&name));
+                let mut node = TreeNode::new(&name, &path, NodeKind::Directory);
+                node.children = children
+                    .into_iter()
+                    .map(|c| c.into_tree_node(Some(&path), files))
+                    .collect();
+                node.size = node.children.iter().map(|c| c.size).sum();
+                node
+            }
+            JsonNode::File {
+                name,
+                path,
+                size,
+                content,
+                note,
+                ..
+            } => {
+                let path = PathBuf::from(path);
+                let mut node = TreeNode::new(&name, &path, NodeKind::File);
+                node.size = size;
+                node.lines = content.as_deref().map(|c| c.lines().count());
+                let content = content.ok_or_else(|| note.unwrap_or_default());
+                files.insert(path, ImportedFile { size, content });
+                node
+            }
+        }
+    }
+
+    pub fn prefix_paths(&mut self, prefix: &str) {
+        match self {
+            JsonNode::Directory { children, .. } => {
+                for child in children {
+                    child.prefix_paths(prefix);
+                }
+            }
+            JsonNode::File { path, .. } => *path = format!("{}/{}", prefix, path),
+        }
+    }
+
+    pub fn insert(&mut self, dirs: &[String], node: JsonNode) {
+        let JsonNode::Directory { children, .. } = self else {
+            return;
+        };
+        let Some((first, rest)) = dirs.split_first() else {
+            children.push(node);
+            return;
+        };
+        let position = children
+            .iter()
+            .position(|c| matches!(c, JsonNode::Directory { name, .. } if name == first));
+        let child = match position {
+            Some(i) => &mut children[i],
+            None => {
+                children.push(JsonNode::directory(first));
+                children.last_mut().unwrap()
+            }
+        };
+        child.insert(rest, node);
+    }
+}
@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+
+use crate::file_utils::FileUtils;
+use crate::git;
+
+const LFS_SPEC_PREFIX: &str = "version https://git-lfs.github.com/spec/";
+const MAX_POINTER_SIZE: usize = 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LfsPointer {
+    pub oid: String,
+    pub size: u64,
+}
+
+impl LfsPointer {
+    pub fn parse(content: &str) -> Option<Self> {
+        if content.len() > MAX_POINTER_SIZE || !content.starts_with(LFS_SPEC_PREFIX) {
+            return None;
+        }
+        let mut oid = None;
+        let mut size = None;
+        for line in content.lines() {
+            if let Some(value) = line.strip_prefix("oid sha256:") {
+                oid = Some(value.trim().to_string());
+            } else if let Some(value) = line.strip_prefix("size ") {
+                size = value.trim().parse().ok();
+            }
+        }
+        Some(LfsPointer {
+            oid: oid?,
+            size: size?,
+        })
+    }
+
+    pub fn local_object(&self, file_path: &Path) -> Option<PathBuf> {
+        if self.oid.len() < 4 {
+            return None;
+        }
+        let object = git::find_git_dir(file_path)?
+            .join("lfs")
+            .join("objects")
+            .join(&self.oid[..2])
+            .join(&self.oid[2..4])
+            .join(&self.oid);
+        object.is_file().then_some(object)
+    }
+
+    pub fn describe(&self) -> String {
+        let short_oid: String = self.oid.chars().take(12).collect();
+        format!(
+            "[LFS object: {}, sha256:{}…]",
+            FileUtils::format_size(self.size),
+            short_oid
+        )
+    }
+}
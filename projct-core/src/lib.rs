@@ -1,7 +1,81 @@
+mod ascii;
+mod audit;
+mod cache;
+mod clipboard;
+mod comments;
 pub mod config;
-pub mod file_utils;
-pub mod generator;
-pub mod gitignore;
+mod config_template;
+mod docs;
+mod doctor;
+mod error;
+mod file_utils;
+mod filters;
+mod generator;
+mod git;
+mod gitignore;
+mod hooks;
+mod json;
+mod lfs;
+mod licenses;
+mod limit;
+mod lint;
+mod lock;
+mod manifest;
+mod naming;
+mod notifications;
+mod permissions;
+mod prefetch;
+mod progress;
+mod redact;
+mod sampling;
+mod selftest;
+mod sha256;
+mod spill;
+mod summary;
+mod throttle;
+mod tokens;
+mod transform;
+mod tree;
+
+/// The scanned project as a tree of nodes, and helpers for the paths inside it.
+pub mod traversal {
+    pub use crate::filters::{relative_path, slash_path};
+    pub use crate::tree::{NodeKind, ProjectTree, TreeNode};
+}
+
+/// Gitignore-style pattern parsing and the checks behind `projct lint-ignores`.
+pub mod ignore {
+    pub use crate::gitignore::GitignoreParser;
+    pub use crate::lint::{lint_ignores, IssueKind, LintIssue};
+}
+
+/// Glob matching for include and exclude patterns, and the built-in pattern lists.
+pub mod filter {
+    pub use crate::filters::{
+        apply_alias, excludes_dir, matches_any, pattern_matches, DEFAULT_EXCLUDE_PATTERNS,
+        DEFAULT_SUMMARIZE_PATTERNS,
+    };
+}
+
+/// Output formats, the JSON tree and its import, and size and text helpers.
+pub mod format {
+    pub use crate::config::{OutputFormat, TreeStyle};
+    pub use crate::file_utils::FileUtils;
+    pub use crate::json::{import, ImportedFile, ImportedTree, JsonNode, JsonOutput};
+}
+
+/// Run statistics, manifests, audits, health checks and cache statistics.
+pub mod report {
+    pub use crate::audit::{audit, Finding, FindingKind};
+    pub use crate::cache::{clear as clear_cache, stats as cache_stats, CacheStats};
+    pub use crate::doctor::{diagnose, Diagnostic, Status};
+    pub use crate::manifest::ManifestEntry;
+    pub use crate::selftest::{selftest, SelftestCheck};
+    pub use crate::summary::RunSummary;
+}
 
 pub use config::{Args, Config};
+pub use error::{Error, Result};
 pub use generator::ProjectTreeGenerator;
+pub use progress::ProgressEvent;
+pub use transform::ContentTransform;
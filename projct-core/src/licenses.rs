@@ -0,0 +1,86 @@
+use regex::Regex;
+
+const SCAN_BYTES: usize = 16 * 1024;
+
+const SPDX_PATTERN: &str = r"SPDX-License-Identifier:\s*([A-Za-z0-9.+\-() ]*[A-Za-z0-9.+)])";
+
+const LICENSE_TEXTS: &[(&str, &[&str])] = &[
+    (
+        "MIT",
+        &["Permission is hereby granted, free of charge, to any person"],
+    ),
+    ("Apache-2.0", &["Apache License", "Version 2.0"]),
+    (
+        "LGPL-3.0",
+        &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 3"],
+    ),
+    (
+        "LGPL-2.1",
+        &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 2.1"],
+    ),
+    (
+        "AGPL-3.0",
+        &["GNU AFFERO GENERAL PUBLIC LICENSE", "Version 3"],
+    ),
+    ("GPL-3.0", &["GNU GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("GPL-2.0", &["GNU GENERAL PUBLIC LICENSE", "Version 2"]),
+    ("MPL-2.0", &["Mozilla Public License", "2.0"]),
+    (
+        "BSD-3-Clause",
+        &[
+            "Redistribution and use in source and binary forms",
+            "Neither the name",
+        ],
+    ),
+    (
+        "BSD-2-Clause",
+        &["Redistribution and use in source and binary forms"],
+    ),
+    (
+        "ISC",
+        &["Permission to use, copy, modify, and/or distribute this software for any"],
+    ),
+    (
+        "Unlicense",
+        &["This is free and unencumbered software released into the public domain"],
+    ),
+];
+
+pub struct LicenseDetector {
+    spdx: Regex,
+}
+
+impl Default for LicenseDetector {
+    fn default() -> Self {
+        LicenseDetector {
+            spdx: Regex::new(SPDX_PATTERN).unwrap(),
+        }
+    }
+}
+
+impl LicenseDetector {
+    pub fn detect(&self, content: &str) -> Vec<String> {
+        let mut end = content.len().min(SCAN_BYTES);
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        let head = &content[..end];
+        let mut found: Vec<String> = self
+            .spdx
+            .captures_iter(head)
+            .map(|c| c[1].trim().to_string())
+            .collect();
+        if found.is_empty() {
+            let normalized = head.split_whitespace().collect::<Vec<_>>().join(" ");
+            if let Some((id, _)) = LICENSE_TEXTS
+                .iter()
+                .find(|(_, phrases)| phrases.iter().all(|phrase| normalized.contains(phrase)))
+            {
+                found.push(id.to_string());
+            }
+        }
+        found.sort();
+        found.dedup();
+        found
+    }
+}
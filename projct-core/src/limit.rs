@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+
+use crate::file_utils::FileUtils;
+
+pub struct LimitWriter<'a> {
+    inner: &'a mut dyn Write,
+    limit: u64,
+    truncate: bool,
+    written: u64,
+    exceeded: bool,
+}
+
+impl<'a> LimitWriter<'a> {
+    pub fn new(inner: &'a mut dyn Write, limit: u64, truncate: bool) -> Self {
+        LimitWriter {
+            inner,
+            limit,
+            truncate,
+            written: 0,
+            exceeded: false,
+        }
+    }
+
+    fn limit_error(&self) -> io::Error {
+        limit_error(self.limit)
+    }
+
+    pub fn truncated_size(&self) -> Option<u64> {
+        (self.exceeded && self.truncate).then_some(self.written)
+    }
+}
+
+pub fn limit_error(limit: u64) -> io::Error {
+    io::Error::other(format!(
+        "output exceeds output.max_total_size ({}); raise the limit, narrow the scan or use --truncate-at-limit",
+        FileUtils::format_size(limit)
+    ))
+}
+
+fn truncation_marker(limit: u64) -> String {
+    format!(
+        "\n[Output truncated: exceeded output.max_total_size of {}]\n",
+        FileUtils::format_size(limit)
+    )
+}
+
+impl Write for LimitWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.exceeded {
+            if self.truncate {
+                return Ok(buf.len());
+            }
+            return Err(self.limit_error());
+        }
+        let remaining = self.limit - self.written;
+        if buf.len() as u64 <= remaining {
+            let written = self.inner.write(buf)?;
+            self.written += written as u64;
+            return Ok(written);
+        }
+        self.exceeded = true;
+        if !self.truncate {
+            return Err(self.limit_error());
+        }
+        let mut end = remaining as usize;
+        while end > 0 && buf[end] & 0b1100_0000 == 0b1000_0000 {
+            end -= 1;
+        }
+        let marker = truncation_marker(self.limit);
+        self.inner.write_all(&buf[..end])?;
+        self.inner.write_all(marker.as_bytes())?;
+        self.written += (end + marker.len()) as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        if self.exceeded && !self.truncate {
+            return Err(self.limit_error());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::gitignore::{relative_slash_path, GitignoreParser};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IssueKind {
+    NeverMatches,
+    Shadowed,
+    Redundant,
+    InsideIgnoredDir,
+}
+
+#[derive(Debug)]
+pub struct LintIssue {
+    pub file: PathBuf,
+    pub line: usize,
+    pub pattern: String,
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            IssueKind::NeverMatches => "never matches any file",
+            IssueKind::Shadowed => "is always overridden by a later pattern",
+            IssueKind::Redundant => "has no effect (earlier patterns already give the same result)",
+            IssueKind::InsideIgnoredDir => "only matches paths inside already-ignored directories",
+        };
+        write!(
+            f,
+            "{}:{}: `{}` {}",
+            self.file.display(),
+            self.line,
+            self.pattern,
+            message
+        )
+    }
+}
+
+#[derive(Default)]
+struct PatternStats {
+    matches: usize,
+    inside_ignored: usize,
+    decided: usize,
+    effective: usize,
+}
+
+pub fn lint_ignores(start_path: &Path) -> Vec<LintIssue> {
+    let mut gitignores = vec![];
+    let mut parsers = HashMap::new();
+    let mut entries = vec![];
+    let walker = WalkDir::new(start_path)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git");
+    for entry in walker.filter_map(|e| e.ok()) {
+        let is_dir = entry.file_type().is_dir();
+        if !is_dir && entry.file_name() == ".gitignore" {
+            let dir = entry.path().parent().unwrap_or(start_path).to_path_buf();
+            gitignores.push((entry.path().to_path_buf(), dir.clone()));
+            parsers.insert(dir, GitignoreParser::new(Some(entry.path())));
+        }
+        entries.push((entry.into_path(), is_dir));
+    }
+
+    let mut stats: HashMap<(&Path, usize), PatternStats> = HashMap::new();
+    let mut ignored_dirs = HashSet::new();
+    for (path, is_dir) in &entries {
+        let parent_ignored = path.parent().is_some_and(|p| ignored_dirs.contains(p));
+        let mut matching = vec![];
+        let dirs: Vec<&Path> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|d| d.starts_with(start_path))
+            .collect();
+        for dir in dirs.into_iter().rev() {
+            let Some((dir, parser)) = parsers.get_key_value(dir) else {
+                continue;
+            };
+            let Some(rel_path) = relative_slash_path(path, dir) else {
+                continue;
+            };
+            for (i, pattern) in parser.patterns().iter().enumerate() {
+                if pattern.matches(&rel_path, *is_dir) {
+                    matching.push((dir.as_path(), i, pattern.is_negative()));
+                }
+            }
+        }
+        for &(dir, i, _) in &matching {
+            let entry = stats.entry((dir, i)).or_default();
+            entry.matches += 1;
+            if parent_ignored {
+                entry.inside_ignored += 1;
+            }
+        }
+        if parent_ignored {
+            if *is_dir {
+                ignored_dirs.insert(path.as_path());
+            }
+            continue;
+        }
+        let Some(&(dir, i, is_negative)) = matching.last() else {
+            continue;
+        };
+        let previous = matching
+            .iter()
+            .rev()
+            .nth(1)
+            .is_some_and(|&(_, _, negative)| !negative);
+        let entry = stats.entry((dir, i)).or_default();
+        entry.decided += 1;
+        if previous == is_negative {
+            entry.effective += 1;
+        }
+        if !is_negative && *is_dir {
+            ignored_dirs.insert(path.as_path());
+        }
+    }
+
+    let mut issues = vec![];
+    for (file, dir) in &gitignores {
+        for (i, pattern) in parsers[dir].patterns().iter().enumerate() {
+            let pattern_stats = stats.get(&(dir.as_path(), i));
+            let kind = match pattern_stats {
+                None => IssueKind::NeverMatches,
+                Some(s) if s.decided == 0 && s.matches == s.inside_ignored => {
+                    IssueKind::InsideIgnoredDir
+                }
+                Some(s) if s.decided == 0 => IssueKind::Shadowed,
+                Some(s) if s.effective == 0 => IssueKind::Redundant,
+                Some(_) => continue,
+            };
+            issues.push(LintIssue {
+                file: file.clone(),
+                line: pattern.line,
+                pattern: pattern.source.clone(),
+                kind,
+            });
+        }
+    }
+    issues
+}
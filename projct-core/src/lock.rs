@@ -0,0 +1,21 @@
+use std::fs::{File, TryLockError};
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+pub fn lock_exclusive(file: &File, path: &Path) -> io::Result<()> {
+    let result = match file.try_lock() {
+        Ok(()) => Ok(()),
+        Err(TryLockError::WouldBlock) => {
+            eprintln!(
+                "Waiting for another projct run to release {}...",
+                path.display()
+            );
+            file.lock()
+        }
+        Err(TryLockError::Error(e)) => Err(e),
+    };
+    match result {
+        Err(e) if e.kind() == ErrorKind::Unsupported => Ok(()),
+        other => other,
+    }
+}
@@ -0,0 +1,63 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+impl ManifestEntry {
+    pub fn skipped(path: &str, size: u64, reason: &str) -> Self {
+        ManifestEntry {
+            path: path.to_string(),
+            size,
+            lines: None,
+            sha256: None,
+            skipped: Some(reason.to_string()),
+        }
+    }
+}
+
+pub fn write(path: &Path, entries: &[ManifestEntry]) -> io::Result<()> {
+    let mut entries = entries.to_vec();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut out = BufWriter::new(File::create(path)?);
+    let csv = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+    if csv {
+        writeln!(out, "path,size,lines,sha256,skipped")?;
+        for entry in &entries {
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                csv_field(&entry.path),
+                entry.size,
+                entry.lines.map(|l| l.to_string()).unwrap_or_default(),
+                entry.sha256.as_deref().unwrap_or(""),
+                csv_field(entry.skipped.as_deref().unwrap_or(""))
+            )?;
+        }
+    } else {
+        serde_json::to_writer_pretty(&mut out, &entries)?;
+        writeln!(out)?;
+    }
+    out.flush()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
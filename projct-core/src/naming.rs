@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::OutputFormat;
+use crate::error::{Error, Result};
+
+pub const DEFAULT_NAME_TEMPLATE: &str = "{stem}{root}{part}.{ext}";
+
+#[derive(Default, Clone, Copy)]
+pub struct OutputName<'a> {
+    pub root: Option<&'a str>,
+    pub part: Option<usize>,
+    pub ext: Option<&'a str>,
+}
+
+pub struct OutputNamer {
+    template: String,
+    dir: PathBuf,
+    stem: String,
+    ext: String,
+    claimed: HashSet<PathBuf>,
+}
+
+impl OutputNamer {
+    pub fn new(filename: &str, template: &str) -> Self {
+        let path = Path::new(filename);
+        OutputNamer {
+            template: template.to_string(),
+            dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            stem: path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            ext: path
+                .extension()
+                .map(|e| e.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            claimed: HashSet::new(),
+        }
+    }
+
+    pub fn render(&self, name: &OutputName) -> PathBuf {
+        let root = name.root.map(|r| format!("-{}", r)).unwrap_or_default();
+        let part = name.part.map(|p| format!(".part{}", p)).unwrap_or_default();
+        let rendered = self
+            .template
+            .replace("{stem}", &self.stem)
+            .replace("{root}", &root)
+            .replace("{part}", &part)
+            .replace("{ext}", name.ext.unwrap_or(&self.ext));
+        self.dir.join(rendered.trim_end_matches('.'))
+    }
+
+    pub fn claim(&mut self, name: &OutputName) -> Result<PathBuf> {
+        let path = self.render(name);
+        let key = std::path::absolute(&path).unwrap_or_else(|_| path.clone());
+        if !self.claimed.insert(key) {
+            return Err(Error::NameCollision { path });
+        }
+        Ok(path)
+    }
+
+    pub fn claimed_names(&self) -> Vec<String> {
+        self.claimed
+            .iter()
+            .filter_map(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .collect()
+    }
+}
+
+pub fn auto_filename(project_name: &str, format: OutputFormat, dated: bool) -> String {
+    if dated {
+        format!("{}-{}.{}", project_name, today_utc(), format.extension())
+    } else {
+        format!("{}.{}", project_name, format.extension())
+    }
+}
+
+pub fn today_utc() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
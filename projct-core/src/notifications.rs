@@ -0,0 +1,127 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::file_utils::FileUtils;
+use crate::summary::RunSummary;
+
+pub struct Completion<'a> {
+    pub output: &'a str,
+    pub summary: RunSummary,
+    pub elapsed: Duration,
+    pub error: Option<String>,
+}
+
+pub fn send(config: &Config, completion: &Completion) {
+    let notifications = &config.notifications;
+    if notifications.desktop {
+        if let Err(e) = desktop(completion) {
+            eprintln!("Cannot show desktop notification: {}", e);
+        }
+    }
+    if let Some(url) = &notifications.webhook {
+        if let Err(e) = webhook(url, config, completion) {
+            eprintln!("Cannot notify webhook {}: {}", url, e);
+        }
+    }
+}
+
+fn message(completion: &Completion) -> (&'static str, String) {
+    if let Some(error) = &completion.error {
+        return ("projct failed", error.clone());
+    }
+    let summary = &completion.summary;
+    (
+        "projct finished",
+        format!(
+            "{} files ({}) in {:.1}s -> {}",
+            summary.files_included,
+            FileUtils::format_size(summary.bytes),
+            completion.elapsed.as_secs_f64(),
+            if completion.output.is_empty() {
+                "-"
+            } else {
+                completion.output
+            }
+        ),
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn desktop(completion: &Completion) -> Result<(), String> {
+    let (title, body) = message(completion);
+    let script = format!(
+        "display notification {:?} with title {:?}",
+        body.replace('\\', "/"),
+        title
+    );
+    run(Command::new("osascript").arg("-e").arg(script), None)
+}
+
+#[cfg(windows)]
+fn desktop(_completion: &Completion) -> Result<(), String> {
+    Err("desktop notifications are not supported on Windows; use a webhook".to_string())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn desktop(completion: &Completion) -> Result<(), String> {
+    let (title, body) = message(completion);
+    run(
+        Command::new("notify-send").args(["--app-name=projct", title, &body]),
+        None,
+    )
+}
+
+fn webhook(url: &str, config: &Config, completion: &Completion) -> Result<(), String> {
+    let payload = serde_json::json!({
+        "status": if completion.error.is_some() { "failed" } else { "ok" },
+        "error": completion.error,
+        "root": config.general.path,
+        "output": completion.output,
+        "elapsed_ms": completion.elapsed.as_millis() as u64,
+        "summary": completion.summary,
+    });
+    run(
+        Command::new("curl").args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--max-time",
+            "10",
+            "--header",
+            "Content-Type: application/json",
+            "--data-binary",
+            "@-",
+            url,
+        ]),
+        Some(payload.to_string().as_bytes()),
+    )
+}
+
+fn run(command: &mut Command, input: Option<&[u8]>) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!("{} is not installed or not on PATH", program),
+            _ => format!("cannot run {}: {}", program, e),
+        })?;
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        stdin.write_all(input).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            stderr.trim()
+        ));
+    }
+    Ok(())
+}
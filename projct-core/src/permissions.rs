@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use crate::config::Filters;
+
+pub struct PermissionFilter {
+    owners: Vec<u32>,
+    required_mode: u32,
+}
+
+impl PermissionFilter {
+    pub fn new(filters: &Filters) -> Result<Option<Self>, String> {
+        if cfg!(not(unix)) || (filters.exclude_owners.is_empty() && filters.required_mode.is_none())
+        {
+            return Ok(None);
+        }
+        let owners = filters
+            .exclude_owners
+            .iter()
+            .map(|owner| resolve_uid(owner).ok_or_else(|| format!("Unknown user '{}'", owner)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let required_mode = match &filters.required_mode {
+            Some(mode) => u32::from_str_radix(mode.trim_start_matches("0o"), 8)
+                .ok()
+                .filter(|&m| m <= 0o7777)
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid permission mode '{}' (expected octal, e.g. 040)",
+                        mode
+                    )
+                })?,
+            None => 0,
+        };
+        Ok(Some(PermissionFilter {
+            owners,
+            required_mode,
+        }))
+    }
+
+    #[cfg(unix)]
+    pub fn excludes(&self, path: &Path) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        let Ok(metadata) = path.metadata() else {
+            return false;
+        };
+        self.owners.contains(&metadata.uid())
+            || metadata.mode() & self.required_mode != self.required_mode
+    }
+
+    #[cfg(not(unix))]
+    pub fn excludes(&self, _path: &Path) -> bool {
+        false
+    }
+}
+
+fn resolve_uid(owner: &str) -> Option<u32> {
+    if let Ok(uid) = owner.parse() {
+        return Some(uid);
+    }
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        if fields.next()? != owner {
+            return None;
+        }
+        fields.nth(1)?.parse().ok()
+    })
+}
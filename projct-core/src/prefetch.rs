@@ -0,0 +1,167 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+use crate::throttle::IoThrottle;
+
+type Slot = Option<io::Result<Vec<u8>>>;
+
+pub struct ReadCache {
+    files: Mutex<(HashMap<PathBuf, Vec<u8>>, u64)>,
+    budget: u64,
+}
+
+impl ReadCache {
+    pub fn new(budget: u64) -> Self {
+        ReadCache {
+            files: Mutex::new((HashMap::new(), 0)),
+            budget,
+        }
+    }
+
+    pub fn fits(&self, size: u64) -> bool {
+        self.budget == 0 || self.files.lock().unwrap().1 + size <= self.budget
+    }
+
+    pub fn insert(&self, path: &Path, bytes: Vec<u8>) {
+        let (files, used) = &mut *self.files.lock().unwrap();
+        let size = bytes.len() as u64;
+        if self.budget > 0 && *used + size > self.budget {
+            return;
+        }
+        *used += size;
+        if let Some(old) = files.insert(path.to_path_buf(), bytes) {
+            *used -= old.len() as u64;
+        }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.lock().unwrap().0.contains_key(path)
+    }
+
+    pub fn get<R>(&self, path: &Path, f: impl FnOnce(&[u8]) -> R) -> Option<R> {
+        self.files.lock().unwrap().0.get(path).map(|bytes| f(bytes))
+    }
+
+    pub fn take(&self, path: &Path) -> Option<Vec<u8>> {
+        let (files, used) = &mut *self.files.lock().unwrap();
+        let bytes = files.remove(path)?;
+        *used -= bytes.len() as u64;
+        Some(bytes)
+    }
+
+    pub fn retain(&self, keep: impl Fn(&Path) -> bool) {
+        let (files, used) = &mut *self.files.lock().unwrap();
+        files.retain(|path, _| keep(path));
+        *used = files.values().map(|bytes| bytes.len() as u64).sum();
+    }
+}
+
+struct Window {
+    consumed: Mutex<usize>,
+    advanced: Condvar,
+    size: usize,
+}
+
+impl Window {
+    fn wait_for(&self, index: usize) -> bool {
+        let mut consumed = self.consumed.lock().unwrap();
+        loop {
+            if *consumed == usize::MAX {
+                return false;
+            }
+            if index < *consumed + self.size {
+                return true;
+            }
+            consumed = self.advanced.wait(consumed).unwrap();
+        }
+    }
+
+    fn advance(&self, consumed: usize) {
+        *self.consumed.lock().unwrap() = consumed;
+        self.advanced.notify_all();
+    }
+}
+
+struct Close<'s>(&'s Window);
+
+impl Drop for Close<'_> {
+    fn drop(&mut self) {
+        self.0.advance(usize::MAX);
+    }
+}
+
+pub struct Prefetched<'s> {
+    receiver: Option<Receiver<(usize, Slot)>>,
+    pending: BTreeMap<usize, Slot>,
+    window: &'s Window,
+}
+
+impl Prefetched<'_> {
+    pub fn take(&mut self, index: usize) -> Slot {
+        let receiver = self.receiver.as_ref()?;
+        while !self.pending.contains_key(&index) {
+            match receiver.recv() {
+                Ok((i, slot)) => {
+                    self.pending.insert(i, slot);
+                }
+                Err(_) => return None,
+            }
+        }
+        self.pending = self.pending.split_off(&index);
+        self.window.advance(index + 1);
+        self.pending.remove(&index).flatten()
+    }
+}
+
+pub fn run<R>(
+    throttle: &IoThrottle,
+    files: &[PathBuf],
+    workers: usize,
+    wanted: impl Fn(&Path) -> bool + Sync,
+    write: impl FnOnce(&mut Prefetched<'_>) -> R,
+) -> R {
+    let window = Window {
+        consumed: Mutex::new(0),
+        advanced: Condvar::new(),
+        size: workers * 2,
+    };
+    if workers <= 1 || files.len() <= 1 {
+        return write(&mut Prefetched {
+            receiver: None,
+            pending: BTreeMap::new(),
+            window: &window,
+        });
+    }
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..workers.min(files.len()) {
+            let sender = sender.clone();
+            let (next, window, wanted) = (&next, &window, &wanted);
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= files.len() || !window.wait_for(index) {
+                    break;
+                }
+                let path = &files[index];
+                let slot = wanted(path).then(|| throttle.read(path));
+                if sender.send((index, slot)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+        let _close = Close(&window);
+        let mut prefetched = Prefetched {
+            receiver: Some(receiver),
+            pending: BTreeMap::new(),
+            window: &window,
+        };
+        write(&mut prefetched)
+    })
+}
@@ -0,0 +1,81 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+const BYTES_WRITTEN_STEP: u64 = 64 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgressEvent {
+    DirectoryEntered { path: PathBuf },
+    FileProcessed { path: PathBuf, bytes: u64 },
+    BytesWritten { total: u64 },
+}
+
+type Callback = Box<dyn FnMut(ProgressEvent) + Send>;
+
+#[derive(Default)]
+struct State {
+    callback: Option<Callback>,
+    written: u64,
+    reported: u64,
+}
+
+#[derive(Clone, Default)]
+pub struct Progress {
+    state: Arc<Mutex<State>>,
+}
+
+impl Progress {
+    pub fn set_callback(&self, callback: impl FnMut(ProgressEvent) + Send + 'static) {
+        self.state.lock().unwrap().callback = Some(Box::new(callback));
+    }
+
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.written = 0;
+        state.reported = 0;
+    }
+
+    pub fn emit(&self, event: ProgressEvent) {
+        if let Some(callback) = self.state.lock().unwrap().callback.as_mut() {
+            callback(event);
+        }
+    }
+
+    fn wrote(&self, bytes: usize, flush: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.written += bytes as u64;
+        let unreported = state.written - state.reported;
+        if unreported >= BYTES_WRITTEN_STEP || (flush && unreported > 0) {
+            state.reported = state.written;
+            let total = state.written;
+            if let Some(callback) = state.callback.as_mut() {
+                callback(ProgressEvent::BytesWritten { total });
+            }
+        }
+    }
+}
+
+pub struct ProgressWriter<'a> {
+    inner: &'a mut dyn Write,
+    progress: &'a Progress,
+}
+
+impl<'a> ProgressWriter<'a> {
+    pub fn new(inner: &'a mut dyn Write, progress: &'a Progress) -> Self {
+        ProgressWriter { inner, progress }
+    }
+}
+
+impl Write for ProgressWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.progress.wrote(written, false);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.progress.wrote(0, true);
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,120 @@
+use regex::Regex;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::config::Redact;
+
+const REDACTED: &str = "[REDACTED]";
+
+const BUILTIN_PATTERNS: &[(&str, &str)] = &[
+    ("AWS access key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+    (
+        "AWS secret key",
+        r#"(?i)aws_secret_access_key\s*[=:]\s*["']?([A-Za-z0-9/+=]{40})"#,
+    ),
+    (
+        "private key",
+        r"(?s)-----BEGIN [A-Z ]*PRIVATE KEY-----.*?-----END [A-Z ]*PRIVATE KEY-----",
+    ),
+    (
+        "credential assignment",
+        r#"(?i)\b(?:password|passwd|pwd|secret|api_?key|access_?token|auth_?token)\b["']?[ \t]*(?::=|[=:])[ \t]*["']([^"'\r\n]+)["']"#,
+    ),
+    ("bearer token", r"(?i)\bbearer\s+([A-Za-z0-9\-._~+/]+=*)"),
+];
+
+const ENV_VALUE_PATTERN: &str = r"(?m)^\s*(?:export\s+)?[A-Za-z_][A-Za-z0-9_]*\s*=\s*(\S.*?)\s*$";
+
+const CONFIG_VALUE_PATTERN: &str = r#"(?im)^\s*(?:-\s+)?["']?(?:[\w.-]*[._-])?(?:password|passwd|pwd|secret|api_?key|access_?token|auth_?token)["']?[ \t]*[=:][ \t]*([^\s"'#;{\[|>&*][^\s#;]*)"#;
+
+const CONFIG_EXTENSIONS: &[&str] = &["ini", "cfg", "conf", "properties", "yaml", "yml", "toml"];
+
+pub struct Redactor {
+    patterns: Vec<(&'static str, Regex)>,
+    env_value: Regex,
+    config_value: Regex,
+}
+
+impl Redactor {
+    pub fn new(redact: &Redact) -> Option<Self> {
+        redact
+            .enabled
+            .then(|| Self::with_patterns(&redact.patterns))
+    }
+
+    pub fn with_patterns(custom: &[String]) -> Self {
+        let patterns = BUILTIN_PATTERNS
+            .iter()
+            .copied()
+            .chain(custom.iter().map(|p| ("custom pattern", p.as_str())))
+            .filter_map(|(label, p)| Regex::new(p).ok().map(|re| (label, re)))
+            .collect();
+        Redactor {
+            patterns,
+            env_value: Regex::new(ENV_VALUE_PATTERN).unwrap(),
+            config_value: Regex::new(CONFIG_VALUE_PATTERN).unwrap(),
+        }
+    }
+
+    pub fn redact(&self, file_path: &Path, content: &str) -> (String, usize) {
+        let mut content = content.to_string();
+        let mut count = 0;
+        if is_env_file(file_path) {
+            count += replace_matches(&self.env_value, &mut content);
+        } else if is_config_file(file_path) {
+            count += replace_matches(&self.config_value, &mut content);
+        }
+        for (_, pattern) in &self.patterns {
+            count += replace_matches(pattern, &mut content);
+        }
+        (content, count)
+    }
+
+    pub fn find(&self, file_path: &Path, content: &str) -> Vec<(Range<usize>, &'static str)> {
+        let mut found = vec![];
+        if is_env_file(file_path) {
+            found.extend(match_ranges(&self.env_value, content).map(|r| (r, ".env value")));
+        } else if is_config_file(file_path) {
+            found.extend(
+                match_ranges(&self.config_value, content).map(|r| (r, "credential assignment")),
+            );
+        }
+        for (label, pattern) in &self.patterns {
+            found.extend(match_ranges(pattern, content).map(|r| (r, *label)));
+        }
+        found
+    }
+}
+
+fn is_env_file(file_path: &Path) -> bool {
+    file_path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .is_some_and(|n| n == ".env" || n.starts_with(".env.") || n.ends_with(".env"))
+}
+
+fn is_config_file(file_path: &Path) -> bool {
+    file_path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| CONFIG_EXTENSIONS.contains(&e.as_str()))
+}
+
+fn match_ranges<'a>(
+    pattern: &'a Regex,
+    content: &'a str,
+) -> impl Iterator<Item = Range<usize>> + 'a {
+    pattern
+        .captures_iter(content)
+        .filter_map(|c| c.get(1).or_else(|| c.get(0)))
+        .filter(|m| !m.is_empty() && m.as_str() != REDACTED)
+        .map(|m| m.range())
+}
+
+fn replace_matches(pattern: &Regex, content: &mut String) -> usize {
+    let ranges: Vec<_> = match_ranges(pattern, content).collect();
+    for range in ranges.iter().rev() {
+        content.replace_range(range.clone(), REDACTED);
+    }
+    ranges.len()
+}
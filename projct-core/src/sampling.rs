@@ -0,0 +1,46 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::config::Sampling;
+use crate::filters;
+use crate::tree::fnv1a;
+
+pub fn unsampled_files(
+    file_list: &[PathBuf],
+    start_path: &Path,
+    sampling: &Sampling,
+) -> HashSet<PathBuf> {
+    let mut groups: BTreeMap<&Path, Vec<&PathBuf>> = BTreeMap::new();
+    for file in file_list {
+        let rel_path = filters::relative_path(file, start_path);
+        if filters::matches_any(&sampling.patterns, &rel_path) {
+            let dir = file.parent().unwrap_or(Path::new(""));
+            groups.entry(dir).or_default().push(file);
+        }
+    }
+    let mut unsampled = HashSet::new();
+    for (dir, mut files) in groups {
+        if files.len() <= sampling.count {
+            continue;
+        }
+        let mut rng = SplitMix64(sampling.seed ^ fnv1a(&filters::relative_path(dir, start_path)));
+        for i in (1..files.len()).rev() {
+            let j = (rng.next() % (i as u64 + 1)) as usize;
+            files.swap(i, j);
+        }
+        unsampled.extend(files[sampling.count..].iter().map(|f| (*f).clone()));
+    }
+    unsampled
+}
+
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
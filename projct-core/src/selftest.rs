@@ -0,0 +1,198 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{Config, OutputFormat};
+use crate::filters;
+use crate::generator::ProjectTreeGenerator;
+use crate::json::{JsonNode, JsonOutput};
+use crate::tree::{NodeKind, ProjectTree};
+
+const FIXTURE: &[(&str, &[u8])] = &[
+    (".gitignore", b"*.log\nbuild/\n!keep.log\n"),
+    ("README.md", b"# Fixture\n"),
+    ("src/main.rs", b"fn main() {}\n"),
+    ("src/nested/deep.rs", b"pub fn deep() {}\n"),
+    ("build/out.txt", b"generated\n"),
+    ("debug.log", b"noise\n"),
+    ("keep.log", b"kept\n"),
+    ("data.bin", &[0, 159, 146, 150, 0, 1]),
+    ("caf\u{e9}.txt", b"unicode\n"),
+];
+
+const EXPECTED_FILES: &[&str] = &[
+    "README.md",
+    "caf\u{e9}.txt",
+    "keep.log",
+    "src/main.rs",
+    "src/nested/deep.rs",
+];
+
+type Check = fn(&Path) -> Result<(), String>;
+
+pub struct SelftestCheck {
+    pub name: &'static str,
+    pub result: Result<(), String>,
+}
+
+pub fn selftest() -> Vec<SelftestCheck> {
+    let fixture = match Fixture::create() {
+        Ok(fixture) => fixture,
+        Err(e) => {
+            return vec![SelftestCheck {
+                name: "create fixture",
+                result: Err(format!("cannot create temporary project: {}", e)),
+            }]
+        }
+    };
+    let checks: [(&'static str, Check); 5] = [
+        ("traversal", check_traversal),
+        ("ignore matching", check_ignores),
+        ("binary detection", check_binary),
+        ("text output", check_text_output),
+        ("json output", check_json_output),
+    ];
+    checks
+        .into_iter()
+        .map(|(name, check)| SelftestCheck {
+            name,
+            result: check(&fixture.root),
+        })
+        .collect()
+}
+
+struct Fixture {
+    root: PathBuf,
+}
+
+impl Fixture {
+    fn create() -> io::Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        let root =
+            std::env::temp_dir().join(format!("projct-selftest-{}-{}", std::process::id(), nanos));
+        let fixture = Fixture { root };
+        for (path, content) in FIXTURE {
+            let path = fixture.root.join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, content)?;
+        }
+        Ok(fixture)
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+fn generator(root: &Path, format: OutputFormat) -> ProjectTreeGenerator {
+    let mut config = Config::default();
+    config.general.path = root.to_string_lossy().to_string();
+    config.output.format = format;
+    ProjectTreeGenerator::new(config)
+}
+
+fn tree_paths(tree: &ProjectTree, root: &Path) -> Vec<String> {
+    let mut paths = vec![];
+    tree.root.walk(&mut |node| {
+        if !node.is_dir() {
+            paths.push(filters::relative_path(&node.path, root));
+        }
+    });
+    paths
+}
+
+fn check_traversal(root: &Path) -> Result<(), String> {
+    let tree = generator(root, OutputFormat::Text).build_tree();
+    let mut files: Vec<String> = tree
+        .files()
+        .iter()
+        .map(|f| filters::relative_path(f, root))
+        .collect();
+    files.sort();
+    if files != EXPECTED_FILES {
+        return Err(format!("expected {:?}, found {:?}", EXPECTED_FILES, files));
+    }
+    Ok(())
+}
+
+fn check_ignores(root: &Path) -> Result<(), String> {
+    let tree = generator(root, OutputFormat::Text).build_tree();
+    let paths = tree_paths(&tree, root);
+    for ignored in ["debug.log", "build/out.txt"] {
+        if paths.iter().any(|p| p == ignored) {
+            return Err(format!("{} should be ignored", ignored));
+        }
+    }
+    if !paths.iter().any(|p| p == "keep.log") {
+        return Err("keep.log is re-included by a negated pattern but missing".to_string());
+    }
+    Ok(())
+}
+
+fn check_binary(root: &Path) -> Result<(), String> {
+    let tree = generator(root, OutputFormat::Text).build_tree();
+    if tree_paths(&tree, root).iter().any(|p| p == "data.bin") {
+        return Err("data.bin should be detected as binary and left out".to_string());
+    }
+    let binary_root = generator(&root.join("data.bin"), OutputFormat::Text).build_tree();
+    if binary_root.root.kind != NodeKind::Binary {
+        return Err(format!(
+            "data.bin should be binary, found {:?}",
+            binary_root.root.kind
+        ));
+    }
+    Ok(())
+}
+
+fn render(root: &Path, format: OutputFormat) -> Result<String, String> {
+    let mut buffer = Vec::new();
+    generator(root, format)
+        .write_to(&mut buffer)
+        .map_err(|e| e.to_string())?;
+    String::from_utf8(buffer).map_err(|e| e.to_string())
+}
+
+fn check_text_output(root: &Path) -> Result<(), String> {
+    let output = render(root, OutputFormat::Text)?;
+    for expected in [
+        "\u{2514}\u{2500}\u{2500} deep.rs",
+        "\nsrc/nested/deep.rs:\npub fn deep() {}\n",
+        "\ncaf\u{e9}.txt:\nunicode\n",
+    ] {
+        if !output.contains(expected) {
+            return Err(format!("output is missing {:?}", expected));
+        }
+    }
+    if output.contains("noise") {
+        return Err("output contains an ignored file".to_string());
+    }
+    Ok(())
+}
+
+fn check_json_output(root: &Path) -> Result<(), String> {
+    let output = render(root, OutputFormat::Json)?;
+    let parsed: JsonOutput = serde_json::from_str(&output).map_err(|e| e.to_string())?;
+    let mut paths = vec![];
+    collect_json_paths(&parsed.root, &mut paths);
+    paths.sort();
+    if paths != EXPECTED_FILES {
+        return Err(format!("expected {:?}, found {:?}", EXPECTED_FILES, paths));
+    }
+    Ok(())
+}
+
+fn collect_json_paths(node: &JsonNode, paths: &mut Vec<String>) {
+    match node {
+        JsonNode::Directory { children, .. } => {
+            for child in children {
+                collect_json_paths(child, paths);
+            }
+        }
+        JsonNode::File { path, .. } => paths.push(path.clone()),
+    }
+}
@@ -0,0 +1,98 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SPILL_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+struct SpillFile {
+    file: BufWriter<File>,
+    path: PathBuf,
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+pub struct SpillBuffer {
+    memory: Vec<u8>,
+    spilled: Option<SpillFile>,
+    limit: u64,
+    write_buffer_size: usize,
+    len: u64,
+}
+
+impl SpillBuffer {
+    pub fn new(limit: u64, write_buffer_size: usize) -> Self {
+        SpillBuffer {
+            memory: vec![],
+            spilled: None,
+            limit,
+            write_buffer_size,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_spilled(&self) -> bool {
+        self.spilled.is_some()
+    }
+
+    pub fn reader(&mut self) -> io::Result<Box<dyn Read + '_>> {
+        match &mut self.spilled {
+            Some(spilled) => {
+                spilled.file.flush()?;
+                spilled.file.get_mut().seek(SeekFrom::Start(0))?;
+                Ok(Box::new(spilled.file.get_ref()))
+            }
+            None => Ok(Box::new(Cursor::new(&self.memory))),
+        }
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "projct-spill-{}-{}",
+            std::process::id(),
+            SPILL_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        let mut spilled = SpillFile {
+            file: BufWriter::with_capacity(self.write_buffer_size, file),
+            path,
+        };
+        spilled.file.write_all(&self.memory)?;
+        self.memory = vec![];
+        self.spilled = Some(spilled);
+        Ok(())
+    }
+}
+
+impl Write for SpillBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.spilled.is_none() && self.limit > 0 && self.len + buf.len() as u64 > self.limit {
+            self.spill()?;
+        }
+        match &mut self.spilled {
+            Some(spilled) => spilled.file.write_all(buf)?,
+            None => self.memory.extend_from_slice(buf),
+        }
+        self.len += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.spilled {
+            Some(spilled) => spilled.file.flush(),
+            None => Ok(()),
+        }
+    }
+}
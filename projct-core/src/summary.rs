@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+use crate::file_utils::FileUtils;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RunSummary {
+    pub directories: usize,
+    pub files_included: usize,
+    pub skipped_ignored: usize,
+    pub skipped_binary: usize,
+    pub skipped_too_big: usize,
+    pub skipped_filtered: usize,
+    pub bytes: u64,
+    pub lines: usize,
+    #[serde(default)]
+    pub excluded_files: usize,
+    #[serde(default)]
+    pub excluded_bytes: u64,
+    #[serde(default)]
+    pub excluded_lines: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncated_size: Option<u64>,
+}
+
+impl RunSummary {
+    pub fn skipped(&self) -> usize {
+        self.skipped_ignored + self.skipped_binary + self.skipped_too_big + self.skipped_filtered
+    }
+
+    pub fn exit_line(&self, output: &str, elapsed: Duration) -> String {
+        let target = match output {
+            "" | "-" => "stdout",
+            path => path,
+        };
+        let (bytes, truncated) = match self.truncated_size {
+            Some(size) => (size, ", truncated at output.max_total_size"),
+            None => (self.bytes, ""),
+        };
+        let tokens = bytes as usize / 4;
+        let tokens = match tokens {
+            0..1_000 => tokens.to_string(),
+            1_000..1_000_000 => format!("{}k", tokens / 1_000),
+            _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+        };
+        format!(
+            "Wrote {} ({} file{}, {}{}, ~{} tokens) in {:.1}s",
+            target,
+            self.files_included,
+            if self.files_included == 1 { "" } else { "s" },
+            FileUtils::format_size(bytes),
+            truncated,
+            tokens,
+            elapsed.as_secs_f64()
+        )
+    }
+
+    pub fn merge(&mut self, other: &RunSummary) {
+        self.directories += other.directories;
+        self.files_included += other.files_included;
+        self.skipped_ignored += other.skipped_ignored;
+        self.skipped_binary += other.skipped_binary;
+        self.skipped_too_big += other.skipped_too_big;
+        self.skipped_filtered += other.skipped_filtered;
+        self.bytes += other.bytes;
+        self.lines += other.lines;
+        self.excluded_files += other.excluded_files;
+        self.excluded_bytes += other.excluded_bytes;
+        self.excluded_lines += other.excluded_lines;
+        self.truncated_size = self.truncated_size.or(other.truncated_size);
+    }
+}
+
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Summary:")?;
+        writeln!(f, "- directories: {}", self.directories)?;
+        writeln!(f, "- files included: {}", self.files_included)?;
+        writeln!(
+            f,
+            "- files skipped: {} ({} ignored, {} binary, {} too big, {} filtered)",
+            self.skipped(),
+            self.skipped_ignored,
+            self.skipped_binary,
+            self.skipped_too_big,
+            self.skipped_filtered
+        )?;
+        writeln!(
+            f,
+            "- total: {}, {} line{}",
+            FileUtils::format_size(self.bytes),
+            self.lines,
+            if self.lines == 1 { "" } else { "s" }
+        )?;
+        if self.excluded_files > 0 {
+            let total = self.bytes + self.excluded_bytes;
+            writeln!(
+                f,
+                "- excluded: {} file{}, {}, {} line{} ({:.1}% of the project is included)",
+                self.excluded_files,
+                if self.excluded_files == 1 { "" } else { "s" },
+                FileUtils::format_size(self.excluded_bytes),
+                self.excluded_lines,
+                if self.excluded_lines == 1 { "" } else { "s" },
+                self.bytes as f64 * 100.0 / total.max(1) as f64
+            )?;
+        }
+        Ok(())
+    }
+}
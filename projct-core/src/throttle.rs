@@ -0,0 +1,139 @@
+use std::fs::File;
+#[cfg(windows)]
+use std::fs::OpenOptions;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const OPEN_RETRIES: u64 = 5;
+
+#[derive(Default)]
+pub struct IoThrottle {
+    max_open: Option<usize>,
+    bytes_per_sec: Option<u64>,
+    open_files: Mutex<usize>,
+    slot_released: Condvar,
+    bytes_read: Mutex<(Option<Instant>, u64)>,
+}
+
+impl IoThrottle {
+    pub fn new(max_open: Option<usize>, bytes_per_sec: Option<u64>) -> Self {
+        IoThrottle {
+            max_open: max_open.filter(|&n| n > 0),
+            bytes_per_sec: bytes_per_sec.filter(|&n| n > 0),
+            ..Default::default()
+        }
+    }
+
+    pub fn open(&self, path: &Path) -> io::Result<ThrottledFile<'_>> {
+        self.acquire_slot();
+        let mut result = open_shared(path);
+        for attempt in 1..=OPEN_RETRIES {
+            match &result {
+                Err(e) if is_too_many_open_files(e) => {
+                    thread::sleep(Duration::from_millis(10 * attempt));
+                    result = open_shared(path);
+                }
+                _ => break,
+            }
+        }
+        match result {
+            Ok(file) => Ok(ThrottledFile {
+                file,
+                throttle: self,
+            }),
+            Err(e) => {
+                self.release_slot();
+                Err(e)
+            }
+        }
+    }
+
+    pub fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.open(path)?.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn acquire_slot(&self) {
+        let Some(max_open) = self.max_open else {
+            return;
+        };
+        let mut open_files = self.open_files.lock().unwrap();
+        while *open_files >= max_open {
+            open_files = self.slot_released.wait(open_files).unwrap();
+        }
+        *open_files += 1;
+    }
+
+    fn release_slot(&self) {
+        if self.max_open.is_none() {
+            return;
+        }
+        let mut open_files = self.open_files.lock().unwrap();
+        *open_files = open_files.saturating_sub(1);
+        self.slot_released.notify_one();
+    }
+
+    fn consume(&self, bytes: usize) {
+        let Some(bytes_per_sec) = self.bytes_per_sec else {
+            return;
+        };
+        let delay = {
+            let mut state = self.bytes_read.lock().unwrap();
+            let started = *state.0.get_or_insert_with(Instant::now);
+            state.1 += bytes as u64;
+            let expected = Duration::from_secs_f64(state.1 as f64 / bytes_per_sec as f64);
+            expected.saturating_sub(started.elapsed())
+        };
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+    }
+}
+
+#[cfg(windows)]
+fn open_shared(path: &Path) -> io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    const FILE_SHARE_READ_WRITE_DELETE: u32 = 0x1 | 0x2 | 0x4;
+    OpenOptions::new()
+        .read(true)
+        .share_mode(FILE_SHARE_READ_WRITE_DELETE)
+        .open(path)
+}
+
+#[cfg(not(windows))]
+fn open_shared(path: &Path) -> io::Result<File> {
+    File::open(path)
+}
+
+pub struct ThrottledFile<'a> {
+    file: File,
+    throttle: &'a IoThrottle,
+}
+
+impl Read for ThrottledFile<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.file.read(buf)?;
+        self.throttle.consume(n);
+        Ok(n)
+    }
+}
+
+impl Drop for ThrottledFile<'_> {
+    fn drop(&mut self) {
+        self.throttle.release_slot();
+    }
+}
+
+fn is_too_many_open_files(e: &io::Error) -> bool {
+    #[cfg(unix)]
+    const EMFILE: i32 = 24;
+    #[cfg(windows)]
+    const EMFILE: i32 = 4;
+    #[cfg(not(any(unix, windows)))]
+    const EMFILE: i32 = -1;
+    e.raw_os_error() == Some(EMFILE)
+}
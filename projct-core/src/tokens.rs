@@ -0,0 +1,62 @@
+#[cfg(feature = "tokens")]
+use tiktoken_rs::CoreBPE;
+
+use crate::config::TokenizerKind;
+
+pub struct TokenCounter {
+    kind: TokenizerKind,
+    bpe: Option<CoreBPE>,
+}
+
+impl TokenCounter {
+    pub fn new(kind: TokenizerKind) -> Self {
+        let bpe = match kind {
+            TokenizerKind::Cl100k => match load_cl100k() {
+                Ok(bpe) => Some(bpe),
+                Err(e) => {
+                    eprintln!(
+                        "[Warning: Cannot load cl100k tokenizer: {}. Using heuristic.]",
+                        e
+                    );
+                    None
+                }
+            },
+            TokenizerKind::Heuristic => None,
+        };
+        TokenCounter { kind, bpe }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match (self.kind, &self.bpe) {
+            (TokenizerKind::Cl100k, Some(_)) => "cl100k",
+            _ => "heuristic",
+        }
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        match &self.bpe {
+            Some(bpe) => bpe.encode_ordinary(text).len(),
+            None => text.chars().count().div_ceil(4),
+        }
+    }
+}
+
+#[cfg(feature = "tokens")]
+fn load_cl100k() -> Result<CoreBPE, String> {
+    tiktoken_rs::cl100k_base().map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "tokens"))]
+enum CoreBPE {}
+
+#[cfg(not(feature = "tokens"))]
+impl CoreBPE {
+    fn encode_ordinary(&self, _text: &str) -> Vec<u32> {
+        match *self {}
+    }
+}
+
+#[cfg(not(feature = "tokens"))]
+fn load_cl100k() -> Result<CoreBPE, String> {
+    Err("built without the tokens feature".to_string())
+}
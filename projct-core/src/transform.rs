@@ -0,0 +1,14 @@
+use std::path::Path;
+
+pub trait ContentTransform: Send + Sync {
+    fn transform(&self, path: &Path, content: String) -> String;
+}
+
+impl<F> ContentTransform for F
+where
+    F: Fn(&Path, String) -> String + Send + Sync,
+{
+    fn transform(&self, path: &Path, content: String) -> String {
+        self(path, content)
+    }
+}
@@ -0,0 +1,124 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::config::AnonymizeMode;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Directory,
+    File,
+    Binary,
+    PathTooLong,
+    Unreadable,
+    Symlink,
+    Submodule,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TreeNode {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: NodeKind,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreeNode>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ProjectTree {
+    pub root: TreeNode,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl TreeNode {
+    pub fn new(name: &str, path: &Path, kind: NodeKind) -> Self {
+        TreeNode {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            kind,
+            size: 0,
+            lines: None,
+            target: None,
+            commit: None,
+            hidden: None,
+            children: vec![],
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self.kind, NodeKind::Directory | NodeKind::Unreadable)
+    }
+
+    pub fn walk<'a>(&'a self, visit: &mut impl FnMut(&'a TreeNode)) {
+        visit(self);
+        for child in &self.children {
+            child.walk(visit);
+        }
+    }
+}
+
+impl ProjectTree {
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = vec![];
+        self.root.walk(&mut |node| {
+            if node.kind == NodeKind::File {
+                files.push(node.path.clone());
+            }
+        });
+        files
+    }
+}
+
+impl ProjectTree {
+    pub fn anonymize(&mut self, mode: AnonymizeMode) {
+        if mode == AnonymizeMode::Off {
+            return;
+        }
+        self.root.name = "project".to_string();
+        self.root.path = PathBuf::new();
+        self.warnings.clear();
+        let mut counters = (0, 0);
+        anonymize_children(&mut self.root, mode, &mut counters);
+    }
+}
+
+fn anonymize_children(node: &mut TreeNode, mode: AnonymizeMode, counters: &mut (usize, usize)) {
+    for child in &mut node.children {
+        let extension = Path::new(&child.name)
+            .extension()
+            .filter(|_| !child.is_dir())
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        let stem = match mode {
+            AnonymizeMode::Hash => format!("{:012x}", fnv1a(&child.name) >> 16),
+            _ if child.is_dir() => {
+                counters.1 += 1;
+                format!("dir_{:03}", counters.1)
+            }
+            _ => {
+                counters.0 += 1;
+                format!("file_{:03}", counters.0)
+            }
+        };
+        child.name = format!("{}{}", stem, extension);
+        child.path = node.path.join(&child.name);
+        child.target = None;
+        anonymize_children(child, mode, counters);
+    }
+}
+
+pub(crate) fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -0,0 +1,164 @@
+use projct_core::config::{default_config_toml, FIELD_DOCS};
+use projct_core::{Args, Config};
+use std::fs;
+
+#[test]
+fn every_setting_is_documented() {
+    let defaults = toml::Table::try_from(Config::default()).unwrap();
+    for (section, values) in &defaults {
+        for key in values.as_table().unwrap().keys() {
+            assert!(
+                FIELD_DOCS
+                    .iter()
+                    .any(|f| f.section == section && f.key == key),
+                "{}.{} has no entry in FIELD_DOCS",
+                section,
+                key
+            );
+        }
+    }
+}
+
+#[test]
+fn default_config_round_trips() {
+    let path = std::env::temp_dir().join(format!("projct-default-{}.toml", std::process::id()));
+    fs::write(&path, default_config_toml()).unwrap();
+    let args = <Args as clap::Parser>::parse_from(["projct"]);
+    let loaded = Config::new(path.to_str().unwrap(), &args, vec![]);
+    let _ = fs::remove_file(&path);
+    let mut loaded = loaded.unwrap();
+    // Paths are resolved relative to the config file's directory
+    loaded.general.path = ".".to_string();
+    loaded.output.filename = "output.txt".to_string();
+    assert_eq!(
+        toml::to_string(&loaded).unwrap(),
+        toml::to_string(&Config::default()).unwrap()
+    );
+}
+
+#[test]
+fn unknown_groups_are_rejected() {
+    let args = <Args as clap::Parser>::parse_from(["projct", "--exclude-group", "vendor"]);
+    let error = Config::new("/nonexistent/projct.toml", &args, vec![])
+        .err()
+        .unwrap();
+    assert!(
+        error
+            .to_string()
+            .contains("unknown group `vendor`: no [groups] defined"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn special_output_names_are_not_resolved_against_the_config_dir() {
+    let dir = std::env::temp_dir().join(format!("projct-auto-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("projct.toml");
+    let args = <Args as clap::Parser>::parse_from(["projct"]);
+    let mut filenames = vec![];
+    for filename in ["auto", "-", "out.txt"] {
+        fs::write(&path, format!("[output]\nfilename = \"{}\"\n", filename)).unwrap();
+        let config = Config::new(path.to_str().unwrap(), &args, vec![]).unwrap();
+        filenames.push(config.output.filename);
+    }
+    let _ = fs::remove_dir_all(&dir);
+    assert_eq!(
+        filenames,
+        ["auto", "-", &dir.join("out.txt").to_string_lossy()]
+    );
+}
+
+#[test]
+fn sizes_accept_units() {
+    let path = std::env::temp_dir().join(format!("projct-sizes-{}.toml", std::process::id()));
+    fs::write(
+        &path,
+        "[general]\nskip_content_over_dir_size = \"50MB\"\n[output]\nmax_total_size = \"4KB\"\n",
+    )
+    .unwrap();
+    let args = <Args as clap::Parser>::parse_from(["projct"]);
+    let loaded = Config::new(path.to_str().unwrap(), &args, vec![]);
+    let _ = fs::remove_file(&path);
+    let loaded = loaded.unwrap();
+    assert_eq!(
+        loaded.general.skip_content_over_dir_size,
+        Some(50 * 1024 * 1024)
+    );
+    assert_eq!(loaded.output.max_total_size, Some(4096));
+
+    let args = <Args as clap::Parser>::parse_from([
+        "projct",
+        "--skip-content-over-dir-size",
+        "1.5 KiB",
+        "--max-total-size",
+        "2MB",
+    ]);
+    let config = Config::new("/nonexistent/projct.toml", &args, vec![]).unwrap();
+    assert_eq!(config.general.skip_content_over_dir_size, Some(1536));
+    assert_eq!(config.output.max_total_size, Some(2 * 1024 * 1024));
+    assert!(<Args as clap::Parser>::try_parse_from([
+        "projct",
+        "--skip-content-over-dir-size",
+        "5 parsecs"
+    ])
+    .is_err());
+}
+
+#[test]
+fn per_extension_size_limits_are_normalized() {
+    let path = std::env::temp_dir().join(format!("projct-ext-sizes-{}.toml", std::process::id()));
+    fs::write(
+        &path,
+        "[output.max_size_by_ext]\n\".JSON\" = 20000\nmd = \"50KB\"\n",
+    )
+    .unwrap();
+    let args = <Args as clap::Parser>::parse_from(["projct"]);
+    let loaded = Config::new(path.to_str().unwrap(), &args, vec![]);
+    let _ = fs::remove_file(&path);
+    let output = loaded.unwrap().output;
+    assert_eq!(
+        output.max_file_size_for(std::path::Path::new("fixtures/big.json")),
+        20000
+    );
+    assert_eq!(
+        output.max_file_size_for(std::path::Path::new("docs/guide.MD")),
+        50 * 1024
+    );
+    assert_eq!(
+        output.max_file_size_for(std::path::Path::new("src/main.rs")),
+        output.max_file_size
+    );
+}
+
+#[test]
+fn tree_style_exclude_flag_prunes_files_and_directories() {
+    let args = <Args as clap::Parser>::parse_from([
+        "projct",
+        "tree",
+        "-I",
+        "node_modules|*.log",
+        "-L",
+        "2",
+    ]);
+    let config = Config::new("/nonexistent/projct.toml", &args, vec![]).unwrap();
+    assert_eq!(
+        config.filters.exclude_patterns,
+        ["node_modules", "node_modules/", "*.log", "*.log/"]
+    );
+    assert_eq!(config.general.max_depth, Some(2));
+}
+
+#[cfg(not(feature = "git"))]
+#[test]
+fn git_settings_need_the_git_feature() {
+    let path = std::env::temp_dir().join(format!("projct-nogit-{}.toml", std::process::id()));
+    fs::write(&path, "[general]\ngit_tracked = true\n").unwrap();
+    let args = <Args as clap::Parser>::parse_from(["projct"]);
+    let loaded = Config::new(path.to_str().unwrap(), &args, vec![]);
+    let _ = fs::remove_file(&path);
+    let error = loaded.unwrap_err().to_string();
+    assert!(error.contains("general.git_tracked"), "{}", error);
+    assert!(<Args as clap::Parser>::try_parse_from(["projct", "--git-tracked"]).is_err());
+}
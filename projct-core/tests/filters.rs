@@ -0,0 +1,160 @@
+use projct_core::filter::{excludes_dir, matches_any, pattern_matches};
+use projct_core::ignore::GitignoreParser;
+use projct_core::traversal::relative_path;
+use std::path::Path;
+
+#[test]
+fn basename_patterns_match_at_any_depth() {
+    assert!(pattern_matches("*.rs", "main.rs"));
+    assert!(pattern_matches("*.rs", "src/a/b/lib.rs"));
+    assert!(!pattern_matches("*.rs", "src/lib.rs.bak"));
+}
+
+#[test]
+fn path_patterns_match_relative_to_start() {
+    assert!(pattern_matches("src/*.rs", "src/main.rs"));
+    assert!(!pattern_matches("src/*.rs", "src/a/main.rs"));
+    assert!(!pattern_matches("src/*.rs", "other/src/main.rs"));
+    assert!(pattern_matches("/src/*.rs", "src/main.rs"));
+}
+
+#[test]
+fn nested_globs_cross_directories() {
+    assert!(pattern_matches("src/**/*.rs", "src/main.rs"));
+    assert!(pattern_matches("src/**/*.rs", "src/a/b/c.rs"));
+    assert!(!pattern_matches("src/**/*.rs", "tests/a.rs"));
+    assert!(pattern_matches("**/fixtures/**", "a/fixtures/data.json"));
+    assert!(pattern_matches("**/fixtures/**", "fixtures/x/y.txt"));
+}
+
+#[test]
+fn matches_any_checks_every_pattern() {
+    let patterns = vec!["*.toml".to_string(), "src/**/*.rs".to_string()];
+    assert!(matches_any(&patterns, "Cargo.toml"));
+    assert!(matches_any(&patterns, "src/x/y.rs"));
+    assert!(!matches_any(&patterns, "README.md"));
+}
+
+#[test]
+fn directory_patterns_prune_directories() {
+    let patterns = vec!["docs/".to_string(), "**/fixtures/**".to_string()];
+    assert!(excludes_dir(&patterns, "docs"));
+    assert!(excludes_dir(&patterns, "src/docs"));
+    assert!(excludes_dir(&patterns, "fixtures"));
+    assert!(excludes_dir(&patterns, "a/b/fixtures"));
+    assert!(!excludes_dir(&patterns, "src"));
+    assert!(!excludes_dir(&["*.log".to_string()], "logs.log"));
+}
+
+#[test]
+fn native_paths_are_matched_with_slashes() {
+    let root = Path::new("project");
+    let file = root.join("src").join("generated").join("out.rs");
+    assert_eq!(relative_path(&file, root), "src/generated/out.rs");
+    let parser = GitignoreParser::from_lines(root, ["src/generated/"]);
+    assert_eq!(
+        parser.matches(&root.join("src").join("generated"), true),
+        Some(true)
+    );
+    assert_eq!(parser.matches(&root.join("src"), true), None);
+}
+
+#[cfg(windows)]
+#[test]
+fn windows_paths_and_patterns_use_forward_slashes() {
+    let root = Path::new(r"C:\project");
+    assert_eq!(
+        relative_path(Path::new(r"C:\project\src\main.rs"), root),
+        "src/main.rs"
+    );
+    assert!(pattern_matches(r"src\*.rs", "src/main.rs"));
+    assert!(excludes_dir(&[r"target\".to_string()], "target"));
+    let parser = GitignoreParser::from_lines(root, ["build/", "docs/*.tmp"]);
+    assert_eq!(
+        parser.matches(Path::new(r"C:\project\build"), true),
+        Some(true)
+    );
+    assert_eq!(
+        parser.matches(Path::new(r"C:\project\docs\a.tmp"), false),
+        Some(true)
+    );
+}
+
+#[test]
+fn gitignore_patterns_match_like_git() {
+    // Expectations checked against `git check-ignore --no-index`.
+    let cases: &[(&str, &str, bool, bool)] = &[
+        ("[Dd]ebug/", "Debug", true, true),
+        ("[Dd]ebug/", "debug", true, true),
+        ("[Dd]ebug/", "Debug", false, false),
+        ("[Dd]ebug/", "src/Debug", true, true),
+        ("[Dd]ebug/", "DEBUG", true, false),
+        ("*.[oa]", "x.o", false, true),
+        ("*.[oa]", "x.c", false, false),
+        ("[!a-c]*.log", "d.log", false, true),
+        ("[!a-c]*.log", "a.log", false, false),
+        ("[^a]b", "xb", false, true),
+        ("[^a]b", "ab", false, false),
+        ("[[:digit:]]*.txt", "1a.txt", false, true),
+        ("[[:digit:]]*.txt", "a1.txt", false, false),
+        ("[a-]x", "-x", false, true),
+        ("[]]x", "]x", false, true),
+        (r"[\]]x", "]x", false, true),
+        (r"[a\-z]", "-", false, true),
+        (r"[a\-z]", "b", false, false),
+        ("[!]]", "a", false, true),
+        ("[!]]", "]", false, false),
+        ("[[:alpha:]-z]", "-", false, true),
+        (r"foo\?.txt", "foo?.txt", false, true),
+        (r"foo\?.txt", "fooa.txt", false, false),
+        (r"\#notes", "#notes", false, true),
+        (r"\!important", "!important", false, true),
+        (r"file\ ", "file ", false, true),
+        (r"\[abc]", "[abc]", false, true),
+        (r"\[abc]", "a", false, false),
+        (r"foo\\", r"foo\", false, true),
+        (r"\*", "*", false, true),
+        (r"\*", "a", false, false),
+        ("trailing  ", "trailing", false, true),
+        ("a/**/b", "a/b", false, true),
+        ("a/**/b", "a/x/y/b", false, true),
+        ("**/node_modules", "x/node_modules", true, true),
+        ("**/node_modules", "node_modules", true, true),
+        ("doc/*.txt", "doc/a.txt", false, true),
+        ("doc/*.txt", "doc/x/a.txt", false, false),
+        ("/root.txt", "root.txt", false, true),
+        ("/root.txt", "sub/root.txt", false, false),
+        ("a?c", "a/c", false, false),
+        ("foo/", "foo", false, false),
+        ("**", "anything/deep", false, true),
+        ("a/**", "a/b/c", false, true),
+        ("a/**", "a", true, false),
+        ("*/b", "a/b", false, true),
+        ("*/b", "x/a/b", false, false),
+        ("x[", "x[", false, false),
+        ("*[", "a[", false, false),
+        ("[a", "[a", false, false),
+        (r"ab\", "ab", false, false),
+    ];
+    let root = Path::new("/project");
+    for &(pattern, path, is_dir, expected) in cases {
+        let parser = GitignoreParser::from_lines(root, [pattern]);
+        let matched = parser.matches(&root.join(path), is_dir) == Some(true);
+        assert_eq!(
+            matched, expected,
+            "pattern {:?} against {:?} (dir: {})",
+            pattern, path, is_dir
+        );
+    }
+}
+
+#[test]
+fn later_gitignore_patterns_win() {
+    let root = Path::new("/project");
+    let parser = GitignoreParser::from_lines(root, ["# comment", "", "*.log", "!keep.log"]);
+    assert_eq!(parser.matches(&root.join("debug.log"), false), Some(true));
+    assert_eq!(parser.matches(&root.join("keep.log"), false), Some(false));
+    assert_eq!(parser.matches(&root.join("main.rs"), false), None);
+    let parser = GitignoreParser::from_lines(root, ["!keep.log", "*.log"]);
+    assert_eq!(parser.matches(&root.join("keep.log"), false), Some(true));
+}
@@ -0,0 +1,1354 @@
+use projct_core::config::OutputFormat;
+use projct_core::{Config, ProgressEvent, ProjectTreeGenerator};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+struct TempProject {
+    root: PathBuf,
+}
+
+impl TempProject {
+    fn new(name: &str, files: &[(&str, &str)]) -> Self {
+        let root =
+            std::env::temp_dir().join(format!("projct-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        TempProject { root }
+    }
+
+    #[cfg(feature = "git")]
+    fn git(&self, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&self.root)
+            .args([
+                "-c",
+                "user.name=Jane Doe",
+                "-c",
+                "user.email=jane@example.com",
+            ])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn config(&self) -> Config {
+        let mut config = Config::default();
+        config.general.path = self.root.to_string_lossy().to_string();
+        config
+    }
+}
+
+impl Drop for TempProject {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+fn render(config: Config) -> String {
+    let mut buffer = Vec::new();
+    ProjectTreeGenerator::new(config)
+        .write_to(&mut buffer)
+        .unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+fn relative_files(config: Config, root: &Path) -> Vec<String> {
+    ProjectTreeGenerator::new(config)
+        .build_tree()
+        .files()
+        .iter()
+        .map(|f| projct_core::traversal::relative_path(f, root))
+        .collect()
+}
+
+#[test]
+fn selftest_passes() {
+    for check in projct_core::report::selftest() {
+        assert!(check.result.is_ok(), "{}: {:?}", check.name, check.result);
+    }
+}
+
+#[test]
+fn directories_sort_before_files() {
+    let project = TempProject::new(
+        "order",
+        &[("b.txt", "b"), ("a.txt", "a"), ("z/inner.txt", "z")],
+    );
+    let files = relative_files(project.config(), &project.root);
+    assert_eq!(files, ["z/inner.txt", "a.txt", "b.txt"]);
+}
+
+#[test]
+fn nested_gitignore_applies_only_below_its_directory() {
+    let project = TempProject::new(
+        "nested-ignore",
+        &[
+            ("sub/.gitignore", "*.tmp\n"),
+            ("sub/a.tmp", "ignored"),
+            ("sub/keep.txt", "kept"),
+            ("top.tmp", "kept"),
+        ],
+    );
+    let files = relative_files(project.config(), &project.root);
+    assert_eq!(files, ["sub/keep.txt", "top.tmp"]);
+}
+
+#[test]
+fn gitignore_hierarchy_follows_git() {
+    let project = TempProject::new(
+        "gitignore-hierarchy",
+        &[
+            (
+                ".gitignore",
+                "*.log\nbuild/\nvendor/**\n!vendor/keep.rs\n**/cache\n/top.txt\n",
+            ),
+            ("top.txt", "ignored at the root only"),
+            ("src/top.txt", "kept"),
+            ("src/.gitignore", "!debug.log\n/local.rs\n"),
+            ("src/debug.log", "re-included by src/.gitignore"),
+            ("src/local.rs", "anchored to src"),
+            ("src/deep/local.rs", "kept"),
+            ("src/deep/cache/x.rs", "ignored at any depth"),
+            ("build/.gitignore", "!out.rs\n"),
+            ("build/out.rs", "parent directory stays ignored"),
+            ("vendor/drop.rs", "ignored"),
+            ("vendor/keep.rs", "re-included"),
+            ("other.log", "ignored"),
+        ],
+    );
+    let files = relative_files(project.config(), &project.root);
+    assert_eq!(
+        files,
+        [
+            "src/deep/local.rs",
+            "src/debug.log",
+            "src/top.txt",
+            "vendor/keep.rs"
+        ]
+    );
+}
+
+#[test]
+fn extra_ignore_files_layer_over_gitignore() {
+    let project = TempProject::new(
+        "extra-ignore",
+        &[
+            (".gitignore", "*.log\n"),
+            (".dockerignore", "dist/\n!keep.log\n"),
+            ("dist/bundle.js", "bundle"),
+            ("debug.log", "noise"),
+            ("keep.log", "kept"),
+            ("main.rs", "fn main() {}"),
+        ],
+    );
+    let mut config = project.config();
+    config.general.extra_ignore_files = vec![".dockerignore".to_string()];
+    config.general.hidden = true;
+    let files = relative_files(config, &project.root);
+    assert_eq!(files, [".dockerignore", "keep.log", "main.rs"]);
+}
+
+#[test]
+fn gitignores_above_the_start_path_apply() {
+    let project = TempProject::new(
+        "ancestor-gitignore",
+        &[
+            (".git/HEAD", "ref: refs/heads/main\n"),
+            (".gitignore", "*.log\n/src/gen/\n"),
+            ("src/.gitignore", "!keep.log\n"),
+            ("src/main.rs", "fn main() {}"),
+            ("src/debug.log", "noise"),
+            ("src/keep.log", "kept"),
+            ("src/gen/out.rs", "generated"),
+        ],
+    );
+    let start = project.root.join("src");
+    let mut config = project.config();
+    config.general.path = start.to_string_lossy().to_string();
+    let files = relative_files(config, &start);
+    assert_eq!(files, ["keep.log", "main.rs"]);
+}
+
+#[test]
+fn hidden_files_are_skipped_unless_requested() {
+    let project = TempProject::new(
+        "hidden",
+        &[
+            (".idea/workspace.xml", "<xml/>"),
+            (".DS_Store", "x"),
+            (".github/ci.yml", "on: push"),
+            ("src/.env.example", "KEY="),
+            ("src/main.rs", "fn main() {}"),
+        ],
+    );
+    let files = relative_files(project.config(), &project.root);
+    assert_eq!(files, ["src/main.rs"]);
+    let mut config = project.config();
+    config.general.hidden = true;
+    let files = relative_files(config, &project.root);
+    assert_eq!(
+        files,
+        [
+            ".github/ci.yml",
+            ".idea/workspace.xml",
+            "src/.env.example",
+            "src/main.rs",
+            ".DS_Store"
+        ]
+    );
+}
+
+#[test]
+fn projctignore_layers_over_gitignore() {
+    let project = TempProject::new(
+        "projctignore",
+        &[
+            (".gitignore", "*.log\n"),
+            (".projctignore", "Cargo.lock\nvendor/\n!keep.log\n"),
+            ("Cargo.lock", "lock"),
+            ("vendor/dep.rs", "dep"),
+            ("debug.log", "noise"),
+            ("keep.log", "kept"),
+            ("main.rs", "fn main() {}"),
+        ],
+    );
+    let files = relative_files(project.config(), &project.root);
+    assert_eq!(files, ["keep.log", "main.rs"]);
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn git_tracked_skips_untracked_files() {
+    let project = TempProject::new(
+        "git-tracked",
+        &[("src/lib.rs", "pub fn f() {}\n"), ("notes.txt", "notes\n")],
+    );
+    project.git(&["init", "-q"]);
+    project.git(&["add", "src/lib.rs"]);
+    fs::create_dir_all(project.root.join("build")).unwrap();
+    fs::write(project.root.join("build/out.js"), "generated").unwrap();
+
+    let mut config = project.config();
+    config.general.git_tracked = true;
+    let files = relative_files(config, &project.root);
+    assert_eq!(files, ["src/lib.rs"]);
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn git_tracked_shows_submodules_with_their_commit() {
+    let project = TempProject::new("git-submodule", &[("src/lib.rs", "pub fn f() {}\n")]);
+    project.git(&["init", "-q"]);
+    project.git(&["add", "src/lib.rs"]);
+    fs::create_dir_all(project.root.join("vendor/lib")).unwrap();
+    fs::write(project.root.join("vendor/lib/inner.rs"), "fn inner() {}\n").unwrap();
+    let commit = "0123456789abcdef0123456789abcdef01234567";
+    project.git(&[
+        "update-index",
+        "--add",
+        "--cacheinfo",
+        &format!("160000,{},vendor/lib", commit),
+    ]);
+
+    let mut config = project.config();
+    config.general.git_tracked = true;
+    let output = render(config.clone());
+    assert!(output.contains("lib [submodule @ 0123456]"), "{}", output);
+    assert!(!output.contains("inner.rs"), "{}", output);
+    assert_eq!(relative_files(config, &project.root), ["src/lib.rs"]);
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn git_tracked_lists_links_checked_out_as_files_as_symlinks() {
+    let project = TempProject::new(
+        "git-core-symlinks",
+        &[("src/lib.rs", "pub fn f() {}\n"), ("link", "src/lib.rs")],
+    );
+    project.git(&["init", "-q"]);
+    project.git(&["config", "core.symlinks", "false"]);
+    project.git(&["add", "src/lib.rs"]);
+    project.git(&[
+        "update-index",
+        "--add",
+        "--cacheinfo",
+        "120000,c7ca8e348707c577d55d8144a801b5108f6c26fd,link",
+    ]);
+
+    let mut config = project.config();
+    config.general.git_tracked = true;
+    let output = render(config.clone());
+    assert!(output.contains("link -> src/lib.rs"), "{}", output);
+    assert!(!output.contains("\nlink:\n"), "{}", output);
+    assert_eq!(relative_files(config, &project.root), ["src/lib.rs"]);
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn changed_since_limits_to_changed_files() {
+    let project = TempProject::new(
+        "changed-since",
+        &[("src/lib.rs", "pub fn f() {}\n"), ("README.md", "old\n")],
+    );
+    project.git(&["init", "-q"]);
+    project.git(&["add", "."]);
+    project.git(&["commit", "-q", "-m", "initial"]);
+    fs::write(project.root.join("README.md"), "new\n").unwrap();
+    fs::write(project.root.join("src/new.rs"), "pub fn g() {}\n").unwrap();
+
+    let mut config = project.config();
+    config.general.changed_since = Some("HEAD".to_string());
+    let files = relative_files(config.clone(), &project.root);
+    assert_eq!(files, ["src/new.rs", "README.md"]);
+
+    config.general.full_tree = true;
+    let output = render(config);
+    assert!(output.contains("lib.rs"), "{}", output);
+    assert!(!output.contains("pub fn f()"), "{}", output);
+    assert!(output.contains("pub fn g()"), "{}", output);
+}
+
+#[cfg(feature = "git")]
+#[test]
+fn commit_info_prefixes_committed_files() {
+    let project = TempProject::new(
+        "commit-info",
+        &[("src/lib.rs", "pub fn f() {}\n"), ("notes.txt", "notes\n")],
+    );
+    project.git(&["init", "-q"]);
+    project.git(&["add", "src/lib.rs"]);
+    project.git(&["commit", "-q", "-m", "initial"]);
+
+    let mut config = project.config();
+    config.output.commit_info = true;
+    let output = render(config);
+    assert!(
+        output.contains("\nsrc/lib.rs:\n[") && output.contains(" by Jane Doe, "),
+        "{}",
+        output
+    );
+    assert!(output.contains("\nnotes.txt:\nnotes\n"), "{}", output);
+}
+
+#[test]
+fn audit_reports_secrets_pii_and_hosts() {
+    let project = TempProject::new(
+        "audit",
+        &[
+            ("config.py", "DB = 'db01.corp'\napi_key = 'abc123'\n"),
+            ("AUTHORS", "Jane <jane@example.com>\n"),
+            ("clean.rs", "fn main() {}\n"),
+        ],
+    );
+    let generator = ProjectTreeGenerator::new(project.config());
+    let findings: Vec<String> = projct_core::report::audit(&generator)
+        .iter()
+        .map(|f| f.to_string())
+        .collect();
+    assert_eq!(
+        findings,
+        [
+            "AUTHORS:1: possible PII (email address)",
+            "config.py:1: internal host (internal hostname)",
+            "config.py:2: possible secret (credential assignment)",
+        ]
+    );
+}
+
+#[test]
+fn redaction_leaves_signatures_alone_and_hides_literals() {
+    let rust = "fn login(password: String, secret: &str) -> bool {\n    let api_key = load();\n    check(password, secret, api_key)\n}\n";
+    let ts = "export function login(password: string, apiKey: string): boolean {\n  const secret: Secret = vault.get();\n  return check(password, secret);\n}\n";
+    let python = "def login(password: str, secret: bytes) -> bool:\n    api_key = load()\n    return check(password, secret, api_key)\n";
+    let project = TempProject::new(
+        "redact-code",
+        &[
+            ("src/auth.rs", rust),
+            ("web/auth.ts", ts),
+            ("auth.py", python),
+            (
+                "settings.py",
+                "PASSWORD = 'hunter2'\nTOKEN = {\"secret\": \"s3 cr3t\"}\n",
+            ),
+            (
+                "app.yaml",
+                "db:\n  password: hunter2\n  password_file: /run/pw\n",
+            ),
+            ("app.ini", "[db]\npassword=hunter2\n"),
+        ],
+    );
+    let output = render(project.config());
+    for source in [rust, ts, python] {
+        assert!(output.contains(source), "{}", output);
+    }
+    assert!(!output.contains("hunter2"), "{}", output);
+    assert!(!output.contains("s3 cr3t"), "{}", output);
+    assert!(
+        output.contains("\n  password: [REDACTED]\n  password_file: /run/pw\n"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn sensitive_files_are_listed_with_a_note_instead_of_contents() {
+    let project = TempProject::new(
+        "sensitive",
+        &[
+            (".env", "DB_PASSWORD=hunter2\n"),
+            ("certs/server.pem", "-----BEGIN CERTIFICATE-----\n"),
+            ("main.rs", "fn main() {}\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.general.hidden = true;
+    config.general.use_gitignore = false;
+    let generator = ProjectTreeGenerator::new(config.clone());
+    let mut buffer = Vec::new();
+    generator.write_to(&mut buffer).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+    let note = "[Excluded: sensitive file, use --no-default-excludes]";
+    assert!(
+        output.contains(&format!("\n.env:\n{}\n", note)),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains(&format!("\ncerts/server.pem:\n{}\n", note)),
+        "{}",
+        output
+    );
+    assert!(!output.contains("BEGIN CERTIFICATE"), "{}", output);
+    assert_eq!(generator.summary().files_included, 1);
+    assert_eq!(generator.summary().skipped_filtered, 2);
+
+    config.filters.default_excludes = false;
+    let output = render(config);
+    assert!(
+        output.contains("\ncerts/server.pem:\n-----BEGIN CERTIFICATE-----\n"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn tree_style_changes_glyphs() {
+    let project = TempProject::new("tree-style", &[("a/b.txt", "b\n"), ("c.txt", "c\n")]);
+    let mut config = project.config();
+    config.output.tree_style = projct_core::config::TreeStyle::Ascii;
+    let output = render(config.clone());
+    assert!(
+        output.contains("\n|-- a/\n|   `-- b.txt\n`-- c.txt\n"),
+        "{}",
+        output
+    );
+
+    config.output.tree_style = projct_core::config::TreeStyle::Indent;
+    let output = render(config);
+    assert!(
+        output.contains("\n  a/\n    b.txt\n  c.txt\n"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn licenses_are_summarized() {
+    let project = TempProject::new(
+        "licenses",
+        &[
+            (
+                "LICENSE",
+                "MIT License\n\nPermission is hereby granted, free of charge, to any\nperson obtaining a copy\n",
+            ),
+            ("src/a.rs", "// SPDX-License-Identifier: Apache-2.0 OR MIT\n"),
+            ("src/b.rs", "fn b() {}\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.output.licenses = true;
+    let output = render(config);
+    assert!(
+        output.contains(
+            "\nLicenses:\n- Apache-2.0 OR MIT (1 file): src/a.rs\n- MIT (1 file): LICENSE\n"
+        ),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn max_total_size_aborts_or_truncates() {
+    let big = "x".repeat(4000);
+    let project = TempProject::new("max-total", &[("a.txt", &big), ("b.txt", &big)]);
+    let mut config = project.config();
+    config.output.max_total_size = Some(5000);
+    let mut buffer = Vec::new();
+    let result = ProjectTreeGenerator::new(config.clone()).write_to(&mut buffer);
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("output.max_total_size"),);
+
+    config.output.truncate_at_limit = true;
+    let generator = ProjectTreeGenerator::new(config);
+    let mut buffer = Vec::new();
+    generator.write_to(&mut buffer).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.len() < 5100, "{}", output.len());
+    assert!(
+        output.ends_with("\n[Output truncated: exceeded output.max_total_size of 4.9 KB]\n"),
+        "{}",
+        output
+    );
+    let summary = generator.summary();
+    assert_eq!(summary.truncated_size, Some(output.len() as u64));
+    let exit_line = summary.exit_line("out.txt", std::time::Duration::ZERO);
+    assert!(
+        exit_line.contains(", truncated at output.max_total_size, "),
+        "{}",
+        exit_line
+    );
+}
+
+#[test]
+fn truncate_lines_keeps_head_and_tail_of_big_files() {
+    let big: String = (1..=2000).map(|i| format!("line {}\n", i)).collect();
+    let project = TempProject::new("truncate-lines", &[("big.txt", &big)]);
+    let mut config = project.config();
+    config.output.max_file_size = 1000;
+    config.output.truncate_lines = Some(projct_core::config::TruncateLines { head: 2, tail: 1 });
+    let output = render(config);
+    assert!(
+        output.contains("\nbig.txt:\nline 1\nline 2\n[... truncated 1,997 lines ...]\nline 2000\n"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn spilled_output_matches_in_memory_output() {
+    let project = TempProject::new(
+        "spill",
+        &[("a.txt", &"a".repeat(3000)), ("b.txt", &"b".repeat(3000))],
+    );
+    let expected = render(project.config());
+    let output_path = std::env::temp_dir().join(format!("projct-spill-{}.txt", std::process::id()));
+    let mut config = project.config();
+    config.io.max_buffer_size = 1024;
+    config.output.filename = output_path.to_string_lossy().to_string();
+    ProjectTreeGenerator::new(config.clone())
+        .generate()
+        .unwrap();
+    let written = fs::read_to_string(&output_path).unwrap();
+    ProjectTreeGenerator::new(config).generate().unwrap();
+    let rewritten = fs::read_to_string(&output_path).unwrap();
+    let _ = fs::remove_file(&output_path);
+    assert_eq!(written, expected);
+    assert_eq!(rewritten, expected);
+}
+
+#[test]
+fn small_write_buffers_produce_the_same_output() {
+    let project = TempProject::new(
+        "write-buffer",
+        &[("a.txt", &"a\n".repeat(500)), ("b.txt", "b\n")],
+    );
+    let expected = render(project.config());
+    for size in [0, 7] {
+        let output_path = std::env::temp_dir().join(format!(
+            "projct-write-buffer-{}-{}.txt",
+            std::process::id(),
+            size
+        ));
+        let mut config = project.config();
+        config.io.write_buffer_size = size;
+        config.io.max_buffer_size = 256;
+        config.output.filename = output_path.to_string_lossy().to_string();
+        ProjectTreeGenerator::new(config).generate().unwrap();
+        let written = fs::read_to_string(&output_path).unwrap();
+        let _ = fs::remove_file(&output_path);
+        assert_eq!(written, expected, "write_buffer_size = {}", size);
+    }
+}
+
+#[test]
+fn sort_by_size_reversed_lists_largest_first() {
+    let project = TempProject::new(
+        "sort-size",
+        &[
+            ("a.txt", "a\n"),
+            ("b.txt", "bbbbbbbbbb\n"),
+            ("c.txt", "ccccc\n"),
+            ("dir/d.txt", "d\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.output.sort = projct_core::config::SortOrder::Size;
+    config.output.reverse = true;
+    let files = relative_files(config, &project.root);
+    assert_eq!(files, ["dir/d.txt", "b.txt", "c.txt", "a.txt"]);
+}
+
+#[test]
+fn contents_follow_tree_order_unless_content_order_is_set() {
+    let project = TempProject::new(
+        "content-order",
+        &[
+            ("b.txt", "bbbbbbbbbb\n"),
+            ("a.txt", "a\n"),
+            ("dir/c.txt", "ccccc\n"),
+        ],
+    );
+    let headers = |config: Config| -> Vec<String> {
+        render(config)
+            .lines()
+            .filter_map(|l| l.strip_suffix(".txt:"))
+            .map(|l| format!("{}.txt", l))
+            .collect()
+    };
+    assert_eq!(headers(project.config()), ["dir/c.txt", "a.txt", "b.txt"]);
+    let mut config = project.config();
+    config.output.content_order = projct_core::config::ContentOrder::Size;
+    assert_eq!(headers(config), ["b.txt", "dir/c.txt", "a.txt"]);
+    let mut config = project.config();
+    config.output.content_order = projct_core::config::ContentOrder::Alpha;
+    assert_eq!(headers(config), ["a.txt", "b.txt", "dir/c.txt"]);
+}
+
+#[test]
+fn xml_docs_wraps_and_escapes_each_file() {
+    let project = TempProject::new(
+        "xml-docs",
+        &[("a.rs", "if a < b && c > d {}\n"), ("b&c.txt", "plain\n")],
+    );
+    let mut config = project.config();
+    config.output.format = projct_core::config::OutputFormat::XmlDocs;
+    let output = render(config);
+    assert!(output.contains("<tree>\n"));
+    assert!(output.contains("b&amp;c.txt\n</tree>"));
+    assert!(output.contains(
+        "<document index=\"1\" path=\"a.rs\">\nif a &lt; b &amp;&amp; c &gt; d {}\n</document>"
+    ));
+    assert!(output.contains("<document index=\"2\" path=\"b&amp;c.txt\">\nplain\n</document>"));
+}
+
+#[test]
+fn count_excluded_reports_filtered_size_and_lines() {
+    let project = TempProject::new(
+        "count-excluded",
+        &[
+            ("src/a.rs", "fn a() {}\n"),
+            ("logs/x.log", "l1\nl2\n"),
+            ("README.md", "# r\n"),
+            (".git/HEAD", "ref: refs/heads/main\n"),
+            (".projct/last-run.toml", "[general]\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.filters.exclude_patterns = vec!["logs/".to_string(), "*.md".to_string()];
+    config.general.count_excluded = true;
+    let generator = ProjectTreeGenerator::new(config);
+    generator.write_to(&mut Vec::new()).unwrap();
+    let summary = generator.summary();
+    assert_eq!(
+        (
+            summary.excluded_files,
+            summary.excluded_bytes,
+            summary.excluded_lines
+        ),
+        (2, 10, 3)
+    );
+    assert!(summary
+        .to_string()
+        .contains("- excluded: 2 files, 10 B, 3 lines (50.0% of the project is included)"));
+}
+
+#[test]
+fn groups_drive_filters_and_content_order() {
+    let project = TempProject::new(
+        "groups",
+        &[
+            ("src/a.rs", "fn a() {}\n"),
+            ("src/a_test.rs", "fn t() {}\n"),
+            ("tests/t.rs", "fn t() {}\n"),
+            ("README.md", "# r\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.groups.insert(
+        "tests".to_string(),
+        vec!["tests/**".to_string(), "**/*_test.*".to_string()],
+    );
+    config
+        .groups
+        .insert("docs".to_string(), vec!["*.md".to_string()]);
+    config.filters.exclude_groups = vec!["tests".to_string()];
+    config.output.group_order = vec!["docs".to_string()];
+    let output = render(config);
+    let headers: Vec<&str> = output
+        .lines()
+        .filter(|l| l.ends_with(".rs:") || l.ends_with(".md:"))
+        .collect();
+    assert_eq!(headers, ["README.md:", "src/a.rs:"], "{}", output);
+}
+
+#[test]
+fn depth_limit_marks_hidden_entries() {
+    let project = TempProject::new(
+        "depth-limit",
+        &[
+            ("a.txt", "a\n"),
+            ("dir/b.txt", "b\n"),
+            ("dir/sub/c.txt", "c\n"),
+            ("dir/.cache/d.txt", "d\n"),
+            ("dir/build/e.txt", "e\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.filters.exclude_patterns = vec!["build/".to_string()];
+    config.general.max_depth = Some(0);
+    let output = render(config);
+    assert!(
+        output.contains("├── dir/\n│   └── … (2 entries hidden, depth limit reached)\n└── a.txt\n"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn git_dir_is_skipped_even_without_gitignore_and_with_hidden_files() {
+    let project = TempProject::new(
+        "git-dir",
+        &[
+            (".git/HEAD", "ref: refs/heads/main\n"),
+            (".gitignore", "*.log\n"),
+            ("sub/.git", "gitdir: ../.git/modules/sub\n"),
+            ("sub/a.txt", "a\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.general.use_gitignore = false;
+    config.general.hidden = true;
+    let output = render(config.clone());
+    assert!(
+        output.contains("├── sub/\n│   └── a.txt\n└── .gitignore\n"),
+        "{}",
+        output
+    );
+    assert!(!output.contains("HEAD"), "{}", output);
+
+    config.general.max_depth = Some(0);
+    let output = render(config);
+    assert!(
+        output.contains("├── sub/\n│   └── … (1 entry hidden, depth limit reached)\n"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn exit_line_names_output_and_quick_stats() {
+    let summary = projct_core::report::RunSummary {
+        files_included: 134,
+        bytes: 812 * 1024,
+        ..Default::default()
+    };
+    assert_eq!(
+        summary.exit_line("output.md", std::time::Duration::from_millis(1400)),
+        "Wrote output.md (134 files, 812.0 KB, ~207k tokens) in 1.4s"
+    );
+    assert!(summary
+        .exit_line("-", std::time::Duration::ZERO)
+        .starts_with("Wrote stdout ("));
+}
+
+#[test]
+fn state_dir_and_config_file_stay_out_of_the_output() {
+    let project = TempProject::new(
+        "own-artifacts",
+        &[
+            (".projct/last-run.toml", "[general]\n"),
+            (
+                "projct.toml",
+                "[notifications]\nwebhook = \"https://secret\"\n",
+            ),
+            ("main.rs", "fn main() {}\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.general.use_gitignore = false;
+    config.source = Some(project.root.join("projct.toml"));
+    assert_eq!(relative_files(config.clone(), &project.root), ["main.rs"]);
+    config.general.exclude_config = false;
+    assert_eq!(
+        relative_files(config, &project.root),
+        ["main.rs", "projct.toml"]
+    );
+}
+
+#[test]
+fn manifest_lists_included_and_skipped_files() {
+    let project = TempProject::new(
+        "manifest",
+        &[
+            ("a.txt", "abc"),
+            ("b.log", "one\ntwo\n"),
+            ("c,d.txt", &"x".repeat(200)),
+        ],
+    );
+    let dir = std::env::temp_dir();
+    let output_path = dir.join(format!("projct-manifest-{}.txt", std::process::id()));
+    let json_path = dir.join(format!("projct-manifest-{}.json", std::process::id()));
+    let csv_path = dir.join(format!("projct-manifest-{}.csv", std::process::id()));
+    let mut config = project.config();
+    config.output.filename = output_path.to_string_lossy().to_string();
+    config.output.max_file_size = 100;
+    config.filters.exclude_patterns = vec!["*.log".to_string()];
+    for manifest in [&json_path, &csv_path] {
+        config.output.manifest = Some(manifest.to_string_lossy().to_string());
+        ProjectTreeGenerator::new(config.clone())
+            .generate()
+            .unwrap();
+    }
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+    let csv = fs::read_to_string(&csv_path).unwrap();
+    for path in [&output_path, &json_path, &csv_path] {
+        let _ = fs::remove_file(path);
+    }
+    assert_eq!(
+        json[0],
+        serde_json::json!({
+            "path": "a.txt",
+            "size": 3,
+            "lines": 1,
+            "sha256": "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        })
+    );
+    assert_eq!(json[1]["skipped"], "filtered");
+    assert_eq!(json[2]["skipped"], "File is too big to show (200 bytes)");
+    assert_eq!(
+        csv,
+        "path,size,lines,sha256,skipped\n\
+         a.txt,3,1,ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad,\n\
+         b.log,8,,,filtered\n\
+         \"c,d.txt\",200,,,File is too big to show (200 bytes)\n"
+    );
+}
+
+#[test]
+fn headers_use_forward_slashes() {
+    let project = TempProject::new("slashes", &[("a/b/c.txt", "deep\n")]);
+    let output = render(project.config());
+    assert!(output.contains("\na/b/c.txt:\ndeep\n"), "{}", output);
+}
+
+#[test]
+fn path_aliases_rewrite_tree_and_headers() {
+    let project = TempProject::new(
+        "aliases",
+        &[("packages/internal-name/src/lib.rs", "pub fn f() {}\n")],
+    );
+    let mut config = project.config();
+    config
+        .output
+        .path_aliases
+        .insert("packages/internal-name".to_string(), "pkg".to_string());
+    let output = render(config);
+    assert!(
+        output.contains("\u{2514}\u{2500}\u{2500} pkg/"),
+        "{}",
+        output
+    );
+    assert!(output.contains("\npkg/src/lib.rs:\n"), "{}", output);
+    assert!(!output.contains("internal-name"), "{}", output);
+}
+
+#[test]
+fn deep_patterns_expand_only_matching_paths() {
+    let project = TempProject::new(
+        "deep",
+        &[
+            ("src/core/a/b/deep.rs", "deep\n"),
+            ("src/other/c/hidden.rs", "hidden\n"),
+            ("src/other/shallow.rs", "shallow\n"),
+            ("top.txt", "top\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.filters.deep_patterns = vec!["src/core/**".to_string()];
+    let output = render(config);
+    assert!(output.contains("deep.rs"), "{}", output);
+    assert!(
+        output.contains("\nsrc/core/a/b/deep.rs:\ndeep\n"),
+        "{}",
+        output
+    );
+    assert!(!output.contains("hidden.rs"), "{}", output);
+    assert!(!output.contains("shallow.rs"), "{}", output);
+    assert!(output.contains("other/"), "{}", output);
+    assert!(!output.contains("top.txt:"), "{}", output);
+}
+
+#[test]
+fn exclude_patterns_skip_directories_and_files() {
+    let project = TempProject::new(
+        "exclude",
+        &[
+            ("src/main.rs", "fn main() {}\n"),
+            ("target/debug/out.rs", "generated\n"),
+            ("notes.log", "log\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.filters.exclude_patterns = vec!["target/".to_string(), "*.log".to_string()];
+    let output = render(config);
+    assert!(!output.contains("target"), "{}", output);
+    assert!(output.contains("src/main.rs:"), "{}", output);
+    assert!(!output.contains("notes.log:"), "{}", output);
+}
+
+#[test]
+fn json_export_can_be_rendered_again() {
+    let project = TempProject::new(
+        "json",
+        &[
+            ("src/lib.rs", "pub fn f() {}\n"),
+            ("README.md", "# Title\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.output.format = OutputFormat::Json;
+    let json_path =
+        std::env::temp_dir().join(format!("projct-test-json-{}.json", std::process::id()));
+    fs::write(&json_path, render(config)).unwrap();
+
+    let mut imported = Config::default();
+    imported.general.from_json = Some(json_path.to_string_lossy().to_string());
+    let output = render(imported);
+    let _ = fs::remove_file(&json_path);
+    assert!(
+        output.contains("\nsrc/lib.rs:\npub fn f() {}\n"),
+        "{}",
+        output
+    );
+    assert!(output.contains("\nREADME.md:\n# Title\n"), "{}", output);
+}
+
+#[test]
+fn progress_events_cover_directories_files_and_bytes() {
+    let project = TempProject::new(
+        "progress",
+        &[
+            ("src/lib.rs", "pub fn f() {}\n"),
+            ("README.md", "# Title\n"),
+        ],
+    );
+    let generator = ProjectTreeGenerator::new(project.config());
+    let events = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&events);
+    generator.on_progress(move |event| recorded.lock().unwrap().push(event));
+    let mut buffer = Vec::new();
+    generator.write_to(&mut buffer).unwrap();
+
+    let events = events.lock().unwrap();
+    let entered = |dir: &Path| {
+        events
+            .iter()
+            .any(|e| matches!(e, ProgressEvent::DirectoryEntered { path } if path == dir))
+    };
+    assert!(entered(&project.root), "{:?}", events);
+    assert!(entered(&project.root.join("src")), "{:?}", events);
+    let processed: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            ProgressEvent::FileProcessed { path, bytes } => Some((path.clone(), *bytes)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        processed,
+        [
+            (project.root.join("src/lib.rs"), 14),
+            (project.root.join("README.md"), 8)
+        ]
+    );
+    assert_eq!(
+        events.last(),
+        Some(&ProgressEvent::BytesWritten {
+            total: buffer.len() as u64
+        })
+    );
+}
+
+#[test]
+fn file_contents_come_from_the_read_made_while_building_the_tree() {
+    let project = TempProject::new(
+        "single-read",
+        &[("a.rs", "// first\n"), ("b.rs", "// second\n")],
+    );
+    let mut config = project.config();
+    config.io.read_workers = 1;
+    let generator = ProjectTreeGenerator::new(config);
+    let (a, b) = (project.root.join("a.rs"), project.root.join("b.rs"));
+    generator.on_progress(move |event| {
+        if matches!(&event, ProgressEvent::FileProcessed { path, .. } if *path == a) {
+            fs::write(&b, "// changed\n").unwrap();
+        }
+    });
+    let mut buffer = Vec::new();
+    generator.write_to(&mut buffer).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+
+    assert!(output.contains("// second\n"), "{}", output);
+    assert!(!output.contains("// changed"), "{}", output);
+}
+
+#[test]
+fn reproducible_output_drops_timings_and_mtime_ordering() {
+    let project = TempProject::new(
+        "reproducible",
+        &[("b.rs", "fn b() {}\n"), ("a.rs", "fn a() {}\n")],
+    );
+    let mut config = project.config();
+    config.general.reproducible = true;
+    config.output.format = OutputFormat::Json;
+    config.output.timings = true;
+    config.output.sort = projct_core::config::SortOrder::Mtime;
+    config.output.content_order = projct_core::config::ContentOrder::Mtime;
+    let first = render(config.clone());
+    assert_eq!(first, render(config));
+    assert!(!first.contains("read_ms"), "{}", first);
+    assert!(first.find("a.rs") < first.find("b.rs"), "{}", first);
+}
+
+#[test]
+fn parallel_reads_keep_tree_order() {
+    let files: Vec<(String, String)> = (0..40)
+        .map(|i| {
+            (
+                format!("dir{}/file{:02}.txt", i % 3, i),
+                format!("line {}\n", i).repeat(i + 1),
+            )
+        })
+        .collect();
+    let files: Vec<(&str, &str)> = files
+        .iter()
+        .map(|(p, c)| (p.as_str(), c.as_str()))
+        .collect();
+    let project = TempProject::new("parallel-reads", &files);
+    let mut serial = project.config();
+    serial.io.read_workers = 1;
+    let mut parallel = project.config();
+    parallel.io.read_workers = 8;
+    assert_eq!(render(serial), render(parallel));
+}
+
+#[test]
+fn oversized_directories_are_listed_without_contents() {
+    let big = "x".repeat(600);
+    let project = TempProject::new(
+        "oversized-dirs",
+        &[
+            ("vendor/a.js", big.as_str()),
+            ("vendor/b.js", big.as_str()),
+            ("src/main.rs", "fn main() {}\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.general.skip_content_over_dir_size = Some(1024);
+    let generator = ProjectTreeGenerator::new(config);
+    let mut buffer = Vec::new();
+    generator.write_to(&mut buffer).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.contains("a.js"), "{}", output);
+    assert!(
+        output.contains("\nvendor/:\n[Contents skipped: directory holds 1.2 KB of included files, over the 1.0 KB limit]\n"),
+        "{}",
+        output
+    );
+    assert!(!output.contains("xxxx"), "{}", output);
+    assert!(
+        output.contains("\nsrc/main.rs:\nfn main() {}\n"),
+        "{}",
+        output
+    );
+    assert_eq!(generator.summary().skipped_too_big, 2);
+}
+
+#[test]
+fn oversized_directories_do_not_stall_prefetching() {
+    let big = "x".repeat(600);
+    let files: Vec<(String, &str)> = (0..10)
+        .map(|i| (format!("big/{}.js", i), big.as_str()))
+        .chain([("src/main.rs".to_string(), "fn main() {}\n")])
+        .collect();
+    let files: Vec<(&str, &str)> = files.iter().map(|(p, c)| (p.as_str(), *c)).collect();
+    let project = TempProject::new("oversized-prefetch", &files);
+    for format in [OutputFormat::Text, OutputFormat::Json] {
+        let mut config = project.config();
+        config.general.skip_content_over_dir_size = Some(1024);
+        config.io.read_workers = 2;
+        config.output.format = format;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || sender.send(render(config)));
+        let output = receiver
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .expect("writer stalled on skipped files");
+        assert!(output.contains("fn main() {}"), "{}", output);
+    }
+}
+
+#[test]
+fn generator_answers_ignored_and_tracked_queries() {
+    let project = TempProject::new(
+        "path-queries",
+        &[
+            (".gitignore", "*.log\n"),
+            ("debug.log", "x\n"),
+            ("main.rs", ""),
+        ],
+    );
+    let generator = ProjectTreeGenerator::new(project.config());
+    assert!(generator.is_ignored(&project.root.join("debug.log"), false));
+    assert!(!generator.is_ignored(&project.root.join("main.rs"), false));
+    assert!(generator.is_tracked(&project.root.join("debug.log"), false));
+}
+
+#[test]
+fn lockfiles_are_summarized() {
+    let project = TempProject::new(
+        "summarize",
+        &[
+            ("Cargo.lock", "[[package]]\nname = \"a\"\n"),
+            ("web/app.min.js", "var a=1;"),
+            ("src/main.rs", "fn main() {}\n"),
+        ],
+    );
+    let output = render(project.config());
+    assert!(
+        output.contains("\nCargo.lock:\n[Summarized: 2 lines, 23 B]\n"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("\nweb/app.min.js:\n[Summarized: 0 lines, 8 B]\n"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("\nsrc/main.rs:\nfn main() {}\n"),
+        "{}",
+        output
+    );
+
+    let mut config = project.config();
+    config.filters.summarize_patterns.clear();
+    let output = render(config);
+    assert!(output.contains("name = \"a\""), "{}", output);
+}
+
+#[test]
+fn size_limits_can_differ_by_extension() {
+    let long = "x".repeat(200);
+    let project = TempProject::new(
+        "size-by-ext",
+        &[("fixture.json", long.as_str()), ("guide.md", long.as_str())],
+    );
+    let mut config = project.config();
+    config.output.max_file_size = 100;
+    config.output.max_size_by_ext = [("md".to_string(), 1000), ("json".to_string(), 50)]
+        .into_iter()
+        .collect();
+    let output = render(config);
+    assert!(
+        output.contains("\nfixture.json:\n[File is too big to show (200 bytes)]\n"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains(&format!("\nguide.md:\n{}\n", long)),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn tree_only_output_counts_entries() {
+    let project = TempProject::new(
+        "tree-only",
+        &[
+            ("src/main.rs", "fn main() {}\n"),
+            ("README.md", "# r\n"),
+            (".env", "KEY=1\n"),
+        ],
+    );
+    let generator = ProjectTreeGenerator::new(project.config());
+    let mut buffer = Vec::new();
+    generator.write_tree_only(&mut buffer, false).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.contains("└── main.rs\n"), "{}", output);
+    assert!(!output.contains("fn main"), "{}", output);
+    assert!(!output.contains(".env"), "{}", output);
+    assert!(output.ends_with("\n1 directory, 2 files\n"), "{}", output);
+
+    let mut buffer = Vec::new();
+    generator.write_tree_only(&mut buffer, true).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.contains("├── \x1b[1;34msrc/\x1b[0m\n"), "{}", output);
+
+    let mut config = project.config();
+    config.general.show_ignored = true;
+    let mut buffer = Vec::new();
+    ProjectTreeGenerator::new(config)
+        .write_tree_only(&mut buffer, false)
+        .unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.contains("── .env\n"), "{}", output);
+}
+
+#[test]
+fn header_and_footer_templates_wrap_the_report() {
+    let project = TempProject::new("header", &[("a.txt", "a\n"), ("src/b.rs", "b\n")]);
+    let mut config = project.config();
+    config.output.header = Some("Review {root}: {file_count} files".to_string());
+    config.output.footer = Some("End of {root}".to_string());
+    let output = render(config);
+    let name = project.root.file_name().unwrap().to_string_lossy();
+    assert!(
+        output.starts_with(&format!("Review {}: 2 files\n\n", name)),
+        "{}",
+        output
+    );
+    assert!(
+        output.ends_with(&format!("\nEnd of {}\n", name)),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn append_adds_to_an_existing_output_file() {
+    let project = TempProject::new("append", &[("a.txt", "a\n")]);
+    let expected = render(project.config());
+    let output_path =
+        std::env::temp_dir().join(format!("projct-append-{}.txt", std::process::id()));
+    fs::write(&output_path, "previous\n").unwrap();
+    let mut config = project.config();
+    config.output.filename = output_path.to_string_lossy().to_string();
+    config.output.append = true;
+    ProjectTreeGenerator::new(config.clone())
+        .generate()
+        .unwrap();
+    ProjectTreeGenerator::new(config).generate().unwrap();
+    let written = fs::read_to_string(&output_path).unwrap();
+    let _ = fs::remove_file(&output_path);
+    assert_eq!(written, format!("previous\n{}{}", expected, expected));
+}
+
+#[test]
+fn strip_comments_keeps_code_and_strings() {
+    let project = TempProject::new(
+        "strip-comments",
+        &[
+            (
+                "lib.rs",
+                "// Copyright 2024\n\n/// Docs.\npub fn f() -> &'static str { /* inline */\n    \"// kept\" // gone\n}\n",
+            ),
+            ("run.py", "#!/usr/bin/env python3\n# License\n\nx = '#1'  # gone\n"),
+            ("notes.txt", "# not code\n\nkept\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.transform.strip_comments = true;
+    let output = render(config);
+    assert!(
+        output.contains("lib.rs:\npub fn f() -> &'static str {\n    \"// kept\"\n}\n"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("run.py:\n#!/usr/bin/env python3\nx = '#1'\n"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("notes.txt:\n# not code\n\nkept\n"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn content_transforms_rewrite_contents_in_order() {
+    struct Shout;
+
+    impl projct_core::ContentTransform for Shout {
+        fn transform(&self, _path: &Path, content: String) -> String {
+            content.to_uppercase()
+        }
+    }
+
+    let project = TempProject::new("transform", &[("a.txt", "hello\n"), ("b.md", "keep\n")]);
+    let mut generator = ProjectTreeGenerator::new(project.config());
+    generator.add_transform(|path: &Path, content: String| {
+        if path.extension().is_some_and(|e| e == "txt") {
+            content.replace("hello", "hello world")
+        } else {
+            content
+        }
+    });
+    generator.add_transform(Shout);
+    let mut buffer = Vec::new();
+    generator.write_to(&mut buffer).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.contains("a.txt:\nHELLO WORLD\n"), "{}", output);
+    assert!(output.contains("b.md:\nKEEP\n"), "{}", output);
+}
+
+#[test]
+fn docs_only_keeps_doc_comments_and_readmes() {
+    let project = TempProject::new(
+        "docs-only",
+        &[
+            (
+                "lib.rs",
+                "//! Crate docs.\n\n/// Adds one.\n#[inline]\npub fn add(a: u32) -> u32 {\n    a + 1\n}\n\nfn private() {}\n",
+            ),
+            (
+                "tool.py",
+                "\"\"\"Tool docs.\"\"\"\n\ndef run(a):\n    \"\"\"Runs.\"\"\"\n    return a\n\ndef helper(): pass\n",
+            ),
+            ("README.md", "# Overview\n"),
+            ("data.toml", "x = 1\n"),
+        ],
+    );
+    let mut config = project.config();
+    config.output.docs_only = true;
+    let output = render(config);
+    assert!(
+        output.contains("lib.rs:\n//! Crate docs.\n\n/// Adds one.\npub fn add(a: u32) -> u32\n"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("tool.py:\n\"\"\"Tool docs.\"\"\"\n\ndef run(a):\n    \"\"\"Runs.\"\"\"\n"),
+        "{}",
+        output
+    );
+    assert!(output.contains("README.md:\n# Overview\n"), "{}", output);
+    assert!(
+        output.contains("data.toml:\n[No doc comments]"),
+        "{}",
+        output
+    );
+    assert!(!output.contains("private"), "{}", output);
+}